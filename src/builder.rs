@@ -0,0 +1,238 @@
+use std::collections::hashmap::HashMap;
+use super::{DFA, NFA, Transition};
+use super::error::{AutomatonError, UnknownState, NoStart, MultipleStarts};
+
+//Named states shared by both builders. States are numbered in the order they are added.
+#[deriving(Clone)]
+struct States {
+    labels: Vec<String>,
+    index: HashMap<String, uint>,
+    start: Vec<uint>,
+    accept: Vec<uint>,
+    transitions: Vec<(String, char, String)>
+}
+
+impl States {
+    fn new() -> States {
+        States {
+            labels: vec!(),
+            index: HashMap::new(),
+            start: vec!(),
+            accept: vec!(),
+            transitions: vec!()
+        }
+    }
+
+    //Returns the number of the state with the given name, adding it if it is new
+    fn add(&mut self, label: &str) -> uint {
+        let label = label.to_string();
+        match self.index.find_copy(&label) {
+            Some(idx) => return idx,
+            None => {}
+        }
+
+        let idx = self.labels.len();
+        self.labels.push(label.clone());
+        self.index.insert(label, idx);
+        idx
+    }
+
+    fn add_start(&mut self, label: &str) {
+        let idx = self.add(label);
+        if !self.start.contains(&idx) {
+            self.start.push(idx);
+        }
+    }
+
+    fn add_accept(&mut self, label: &str) {
+        let idx = self.add(label);
+        if !self.accept.contains(&idx) {
+            self.accept.push(idx);
+        }
+    }
+
+    fn start(&self) -> Result<uint, AutomatonError> {
+        match self.start.len() {
+            0 => Err(NoStart),
+            1 => Ok(*self.start.get(0)),
+            _ => Err(MultipleStarts(self.labels.get(*self.start.get(0)).clone(),
+                                    self.labels.get(*self.start.get(1)).clone()))
+        }
+    }
+
+    fn find(&self, label: &String) -> Result<uint, AutomatonError> {
+        match self.index.find_copy(label) {
+            Some(idx) => Ok(idx),
+            None => Err(UnknownState(label.clone()))
+        }
+    }
+
+    //The transitions with names replaced by state numbers
+    fn numbered_transitions(&self) -> Result<Vec<Transition>, AutomatonError> {
+        let mut transitions = vec!();
+        for &(ref from, sym, ref to) in self.transitions.iter() {
+            transitions.push((try!(self.find(from)), sym, try!(self.find(to))));
+        }
+
+        Ok(transitions)
+    }
+}
+
+/// Builds a DFA whose states are named instead of numbered.
+///
+/// States are added by name, and transitions, the start state and accept states
+/// refer to those names. The built DFA keeps the names for printing and traces.
+#[deriving(Clone)]
+pub struct DfaBuilder {
+    alphabet: Vec<char>,
+    states: States,
+    partial: bool
+}
+
+impl DfaBuilder {
+    /// Creates a builder for a DFA over alphabet with no states.
+    pub fn new(alphabet: &Vec<char>) -> DfaBuilder {
+        DfaBuilder { alphabet: alphabet.clone(), states: States::new(), partial: false }
+    }
+
+    /// Adds a state with the given name, if there is none yet.
+    pub fn state<'a>(&'a mut self, label: &str) -> &'a mut DfaBuilder {
+        self.states.add(label);
+        self
+    }
+
+    /// Adds a state with the given name, if there is none yet, and makes it the start state.
+    pub fn start_state<'a>(&'a mut self, label: &str) -> &'a mut DfaBuilder {
+        self.states.add_start(label);
+        self
+    }
+
+    /// Adds a state with the given name, if there is none yet, and makes it an accept state.
+    pub fn accept_state<'a>(&'a mut self, label: &str) -> &'a mut DfaBuilder {
+        self.states.add_accept(label);
+        self
+    }
+
+    /// Adds a transition between the named states on sym.
+    ///
+    /// Both states must be added before the DFA is built.
+    pub fn transition<'a>(&'a mut self, from: &str, sym: char, to: &str) -> &'a mut DfaBuilder {
+        self.states.transitions.push((from.to_string(), sym, to.to_string()));
+        self
+    }
+
+    /// Adds a transition between the named states on each of syms.
+    pub fn transitions<'a>(&'a mut self, from: &str, syms: &[char], to: &str) -> &'a mut DfaBuilder {
+        for &sym in syms.iter() {
+            self.transition(from, sym, to);
+        }
+
+        self
+    }
+
+    /// Allows missing transitions, which go to an implicit dead state as in `DFA::new_partial`.
+    pub fn partial<'a>(&'a mut self) -> &'a mut DfaBuilder {
+        self.partial = true;
+        self
+    }
+
+    /// Builds the DFA.
+    ///
+    /// States are numbered in the order they were first added, which is the numbering
+    /// used by errors from `DFA::new`. Returns an Err if a transition names a state that
+    /// was not added or if there is not exactly one start state.
+    pub fn build(&self) -> Result<DFA, AutomatonError> {
+        let start = try!(self.states.start());
+        let transitions = try!(self.states.numbered_transitions());
+        let num_states = self.states.labels.len();
+
+        let dfa = if self.partial {
+            try!(DFA::new_partial(num_states, &self.alphabet, &transitions, start, &self.states.accept))
+        }
+
+        else {
+            try!(DFA::new(num_states, &self.alphabet, &transitions, start, &self.states.accept))
+        };
+
+        Ok(dfa.with_labels(self.states.labels.clone()))
+    }
+}
+
+/// Builds an NFA whose states are named instead of numbered.
+///
+/// Works like `DfaBuilder`, and can also add epsilon transitions.
+/// Every char, including '_', can be a symbol.
+#[deriving(Clone)]
+pub struct NfaBuilder {
+    alphabet: Vec<char>,
+    states: States,
+    epsilons: Vec<(String, String)>
+}
+
+impl NfaBuilder {
+    /// Creates a builder for an NFA over alphabet with no states.
+    pub fn new(alphabet: &Vec<char>) -> NfaBuilder {
+        NfaBuilder { alphabet: alphabet.clone(), states: States::new(), epsilons: vec!() }
+    }
+
+    /// Adds a state with the given name, if there is none yet.
+    pub fn state<'a>(&'a mut self, label: &str) -> &'a mut NfaBuilder {
+        self.states.add(label);
+        self
+    }
+
+    /// Adds a state with the given name, if there is none yet, and makes it the start state.
+    pub fn start_state<'a>(&'a mut self, label: &str) -> &'a mut NfaBuilder {
+        self.states.add_start(label);
+        self
+    }
+
+    /// Adds a state with the given name, if there is none yet, and makes it an accept state.
+    pub fn accept_state<'a>(&'a mut self, label: &str) -> &'a mut NfaBuilder {
+        self.states.add_accept(label);
+        self
+    }
+
+    /// Adds a transition between the named states on sym.
+    ///
+    /// Both states must be added before the NFA is built.
+    pub fn transition<'a>(&'a mut self, from: &str, sym: char, to: &str) -> &'a mut NfaBuilder {
+        self.states.transitions.push((from.to_string(), sym, to.to_string()));
+        self
+    }
+
+    /// Adds a transition between the named states on each of syms.
+    pub fn transitions<'a>(&'a mut self, from: &str, syms: &[char], to: &str) -> &'a mut NfaBuilder {
+        for &sym in syms.iter() {
+            self.transition(from, sym, to);
+        }
+
+        self
+    }
+
+    /// Adds an epsilon transition between the named states.
+    pub fn epsilon<'a>(&'a mut self, from: &str, to: &str) -> &'a mut NfaBuilder {
+        self.epsilons.push((from.to_string(), to.to_string()));
+        self
+    }
+
+    /// Builds the NFA.
+    ///
+    /// States are numbered in the order they were first added, which is the numbering
+    /// used by errors from `NFA::with_epsilons`. Returns an Err if a transition names a state
+    /// that was not added or if there is not exactly one start state.
+    pub fn build(&self) -> Result<NFA, AutomatonError> {
+        let start = try!(self.states.start());
+        let transitions = try!(self.states.numbered_transitions());
+        let num_states = self.states.labels.len();
+
+        let mut epsilons = vec!();
+        for &(ref from, ref to) in self.epsilons.iter() {
+            epsilons.push((try!(self.states.find(from)), try!(self.states.find(to))));
+        }
+
+        let nfa = try!(NFA::with_epsilons(num_states, &self.alphabet, &transitions, &epsilons,
+                                          start, &self.states.accept));
+        Ok(nfa.with_labels(self.states.labels.clone()))
+    }
+}
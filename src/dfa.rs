@@ -1,118 +1,471 @@
 use std::collections::hashmap::{HashSet, HashMap};
 use std::collections::bitv::BitvSet;
+use std::collections::ringbuf::RingBuf;
+use std::collections::Deque;
 use std::fmt;
+use std::mem;
 use std::cmp::PartialEq;
-use super::{Run, Transition};
+use std::num::{Zero, One, FromPrimitive, Signed};
+use std::rand::Rng;
+use num::bigint::{BigUint, BigInt, Plus, RandBigInt};
+use super::{Run, Word, Symbol, Transition, NFA, Strings};
+use super::error::{AutomatonError, NoStates, TransitionCount, UnknownSymbol};
+use super::error::{StateOutOfRange, DuplicateTransition, MissingTransition, InvalidStart};
+use super::error::{InvalidAccept, TooManyStates, ValidationReport};
+use super::validate;
+use super::graph;
+use super::graph::{NoPath, Finite, Unbounded};
+use super::regex::{Regex, Empty, Epsilon, Literal};
+use super::regex::{EliminationOrder, InOrder, FewestPaths, LeastWeight};
 
 /// Deterministic Finite Automata
 ///
 /// A DFA is comprised of a set of states and an alphabet
 /// of symbols. Each state has a transition from itself to
 /// some other state for each symbol in the alphabet. 
+/// A partial DFA may omit transitions, which then go to an
+/// implicit dead state that rejects every string.
 ///
 /// A DFA executes an input string by starting from the start state
 /// and reading the string one symbol at a time. For each symbol, it
 /// chages states based on the specified transitions.
 /// A DFA "accepts" a string if it ends in any accept state after reading
 /// the entire input.
+///
+/// Symbols are chars by default, but can be any `Symbol` such as bytes or the
+/// variants of an enum. Methods that build strings or regular expressions are only
+/// available for chars.
+///
+/// Transitions are stored in a flat table with one row per state and one column per
+/// symbol, so each step of `run` is a binary search for the symbol's column and an array lookup.
+/// States are numbered with 32 bits, so a DFA has fewer than 2^32 - 1 states.
 #[deriving(Clone)]
-pub struct DFA {
+pub struct DFA<S = char> {
     start: uint,
-    alphabet: Vec<char>,
-    delta: HashMap<(uint, char), uint>,
+    alphabet: Vec<S>,
+    //The alphabet in sorted order, with the position of each symbol in alphabet
+    columns: Vec<(S, uint)>,
+    //table.get(state * alphabet.len() + col) is the next state from state on alphabet.get(col),
+    //or DEAD if a partial DFA has no such transition
+    table: Vec<u32>,
     accept: BitvSet,
-    num_states: uint
+    num_states: uint,
+    //Names of the states, if they were given any
+    labels: Option<Vec<String>>
 }
 
-impl DFA {
+//Marks a missing transition in the table, so every state number is below it
+static DEAD: u32 = 0xFFFFFFFF;
+
+impl<S: Symbol> DFA<S> {
     /// Creates a new DFA
     ///
     /// Returns an Err if there is a transition on a state or symbol that
-    /// does not exist, or if there is not **exactly** one transition for each
-    /// combination of state and input symbol.
+    /// does not exist, if the start or an accept state does not exist, if there
+    /// is not **exactly** one transition for each combination of state and input symbol,
+    /// or if there are too many states to number.
     pub fn new(
         num_states: uint,
-        alphabet: &Vec<char>,
-        transitions: &Vec<Transition>,
+        alphabet: &Vec<S>,
+        transitions: &Vec<Transition<S>>,
         start: uint,
         accept: &Vec<uint>
-    ) -> Result<DFA, String> {
+    ) -> Result<DFA<S>, AutomatonError<S>> {
 
         if num_states == 0 {
-            return Err(format!("Must contain at least one state"));
+            return Err(NoStates);
         }
 
-        let dfa_size = num_states * alphabet.len();
-
         // Check that DFA has the proper number of transitions
+        let dfa_size = num_states * alphabet.len();
         if transitions.len() != dfa_size {
-            return Err(format!("Incorrect number of transitions"));
+            return Err(TransitionCount(transitions.len(), dfa_size));
         }
 
-        if start >= num_states {
-            return Err(format!("Invalid start state"));
+        // With the right number of transitions and no duplicates, every
+        // (state, sym) pair has exactly one transition.
+        DFA::build(num_states, alphabet, transitions, start, accept)
+    }
+
+    /// Creates a new partial DFA
+    ///
+    /// Each combination of state and input symbol may have at most one transition.
+    /// Missing transitions go to an implicit dead state that rejects every string.
+    ///
+    /// Returns an Err if there is a transition on a state or symbol that
+    /// does not exist, a duplicate transition, or too many states to number.
+    pub fn new_partial(
+        num_states: uint,
+        alphabet: &Vec<S>,
+        transitions: &Vec<Transition<S>>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> Result<DFA<S>, AutomatonError<S>> {
+
+        if num_states == 0 {
+            return Err(NoStates);
+        }
+
+        DFA::build(num_states, alphabet, transitions, start, accept)
+    }
+
+    /// Checks a description of a DFA for every problem at once.
+    ///
+    /// Takes the same arguments as `DFA::new`, which succeeds exactly when the report has
+    /// no errors. Missing (state, symbol) pairs are reported individually.
+    /// Unreachable and dead states are reported as warnings.
+    pub fn validate(
+        num_states: uint,
+        alphabet: &Vec<S>,
+        transitions: &Vec<Transition<S>>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> ValidationReport<S> {
+
+        let mut report = ValidationReport { errors: vec!(), warnings: vec!() };
+        if num_states == 0 {
+            report.errors.push(NoStates);
+            return report;
+        }
+
+        if num_states >= DEAD as uint {
+            report.errors.push(TooManyStates(num_states));
+            return report;
         }
 
-        let mut trns_fn = HashMap::with_capacity(dfa_size);
+        let written = transitions.iter().map(|&(curr, ref sym, next)| (curr, Some(sym.clone()), next));
+        let valid = validate::check_transitions(&mut report, num_states, alphabet, written,
+                                                |t, state| validate::out_of_range(t, state));
+
+        let mut seen = HashSet::new();
+        let mut edges = vec!();
+        for &(curr, ref sym, next) in valid.iter() {
+            let sym = sym.clone().unwrap();
+            if seen.insert((curr, sym.clone())) {
+                edges.push((curr, next));
+            }
 
-        // We need to check that each (state, sym) transiton occurs exactly once.
-        // We create a second hash initialized with the values we still need to see, and remove
-        // one each time we add it to the transition function.
-        // If one is missing, there is a duplicate function, as we already validated that there are only
-        // states*symbols transitions given.
+            else {
+                report.errors.push(DuplicateTransition((curr, sym, next)));
+            }
+        }
 
-        let mut permutation = HashSet::with_capacity(dfa_size);
         for i in range(0, num_states) {
             for sym in alphabet.iter() {
-                permutation.insert((i, sym.clone()));
+                if !seen.contains(&(i, sym.clone())) {
+                    report.errors.push(MissingTransition(i, sym.clone()));
+                }
             }
         }
 
-        // Validate transitions and add them to the transition table
-        for &(curr, sym, next) in transitions.iter() {
-            if !alphabet.contains(&sym) {
-                return Err(format!("Symbol `{}` is not in the alphabet", sym));
+        validate::check_states(&mut report, num_states, start, accept, &edges);
+        report
+    }
+
+    //Validates the start state and transitions and builds the transition table.
+    //Does not check that the transition function is complete.
+    fn build(
+        num_states: uint,
+        alphabet: &Vec<S>,
+        transitions: &Vec<Transition<S>>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> Result<DFA<S>, AutomatonError<S>> {
+
+        if num_states >= DEAD as uint {
+            return Err(TooManyStates(num_states));
+        }
+
+        if start >= num_states {
+            return Err(InvalidStart(start));
+        }
+
+        let mut accept_states = BitvSet::new();
+        for &s in accept.iter() {
+            if s >= num_states {
+                return Err(InvalidAccept(s));
             }
 
+            accept_states.insert(s);
+        }
+
+        let width = alphabet.len();
+        let table = Vec::from_elem(num_states * width, DEAD);
+        let mut dfa = DFA::from_table(num_states, alphabet.clone(), table, start, accept_states, None);
+
+        // Validate transitions and add them to the transition table
+        for t in transitions.iter() {
+            let &(curr, ref sym, next) = t;
+            let col = match dfa.column(sym) {
+                Some(col) => col,
+                None => return Err(UnknownSymbol(t.clone()))
+            };
+
             if curr >= num_states {
-                return Err(format!("In transition: ({}, '{}') -> {}: State `{}` \
-                                    does not exist", curr, sym, next, curr));
+                return Err(StateOutOfRange(t.clone(), curr));
             }
 
             if next >= num_states {
-                return Err(format!("In transition: ({}, '{}') -> {}: State `{}` \
-                                    does not exist", curr, sym, next, next));
+                return Err(StateOutOfRange(t.clone(), next));
             }
 
-            if permutation.contains(&(curr, sym)) {
-                trns_fn.insert((curr, sym), next);
-                permutation.remove(&(curr,sym));
+            let entry = dfa.table.get_mut(curr * width + col);
+            if *entry != DEAD {
+                return Err(DuplicateTransition(t.clone()));
             }
 
-            else {
-                return Err(format!("Duplicate transition: ({}, '{}') -> {}", curr, sym, next));
-            }
+            *entry = next as u32;
         }
 
-        let mut accept_states = BitvSet::new();
-        for i in accept.iter() {
-            accept_states.insert(*i);
-        }
+        Ok(dfa)
+    }
+
+    //Assembles a DFA around a filled in transition table, indexing the columns of its symbols.
+    //Every state must be below DEAD.
+    fn from_table(
+        num_states: uint,
+        alphabet: Vec<S>,
+        table: Vec<u32>,
+        start: uint,
+        accept: BitvSet,
+        labels: Option<Vec<String>>
+    ) -> DFA<S> {
+
+        assert!(num_states < DEAD as uint, "A DFA must have fewer than {} states", DEAD);
+        let mut columns: Vec<(S, uint)> = alphabet.iter().enumerate().map(|(col, sym)| (sym.clone(), col)).collect();
+        columns.sort();
 
-        Ok(DFA{
-            accept: accept_states, 
+        DFA {
+            accept: accept,
             start: start,
-            alphabet: alphabet.clone(),
-            delta: trns_fn,
-            num_states: num_states
+            alphabet: alphabet,
+            columns: columns,
+            table: table,
+            num_states: num_states,
+            labels: labels
+        }
+    }
+
+    //Returns the column of sym in the table, or None if it is not in the alphabet
+    fn column(&self, sym: &S) -> Option<uint> {
+        self.columns.as_slice().bsearch(|&(ref s, _)| s.cmp(sym)).map(|i| {
+            let &(_, col) = self.columns.get(i);
+            col
         })
     }
 
+    //Returns the next state from state on the symbol in column col, or None if there is no transition
+    fn next(&self, state: uint, col: uint) -> Option<uint> {
+        let next = *self.table.get(state * self.alphabet.len() + col);
+        if next == DEAD {
+            None
+        }
+
+        else {
+            Some(next as uint)
+        }
+    }
+
+    //Every transition, as (current state, column, next state)
+    fn transitions(&self) -> Vec<(uint, uint, uint)> {
+        let mut transitions = vec!();
+        for curr in range(0, self.num_states) {
+            for col in range(0, self.alphabet.len()) {
+                match self.next(curr, col) {
+                    Some(next) => transitions.push((curr, col, next)),
+                    None => {}
+                }
+            }
+        }
+
+        transitions
+    }
+
+    /// Returns true if every state has a transition on every symbol of the alphabet.
+    pub fn is_complete(&self) -> bool {
+        !self.table.iter().any(|&next| next == DEAD)
+    }
+
+    /// Returns an equivalent complete DFA.
+    ///
+    /// If self is partial, the implicit dead state becomes an explicit state, numbered
+    /// after all existing states, that every missing transition goes to.
+    pub fn complete(&self) -> DFA<S> {
+        let mut dfa = self.clone();
+        if self.is_complete() {
+            return dfa;
+        }
+
+        let dead = self.num_states as u32;
+        dfa.num_states += 1;
+        dfa.table.grow(self.alphabet.len(), &DEAD);
+        for next in dfa.table.mut_iter() {
+            if *next == DEAD {
+                *next = dead;
+            }
+        }
+
+        dfa
+    }
+
+    /// Returns an equivalent partial DFA containing only useful states.
+    ///
+    /// A state is useful if it is reachable from the start state and some accept state
+    /// is reachable from it. The start state is always kept. Transitions to removed
+    /// states go to the implicit dead state instead.
+    pub fn trim(&self) -> DFA<S> {
+        let mut useful = self.reachable_states();
+        useful.intersect_with(&self.coreachable_states());
+        useful.insert(self.start);
+
+        //Renumber the useful states in order
+        let mut state_map = HashMap::new();
+        for (idx, s) in useful.iter().enumerate() {
+            state_map.insert(s, idx);
+        }
+
+        let width = self.alphabet.len();
+        let mut table = Vec::from_elem(state_map.len() * width, DEAD);
+        for &(curr, col, next) in self.transitions().iter() {
+            match (state_map.find_copy(&curr), state_map.find_copy(&next)) {
+                (Some(c), Some(n)) => *table.get_mut(c * width + col) = n as u32,
+                _ => {}
+            }
+        }
+
+        let mut accept = BitvSet::new();
+        for s in self.accept.iter() {
+            match state_map.find_copy(&s) {
+                Some(idx) => { accept.insert(idx); },
+                None => {}
+            }
+        }
+
+        let labels: Option<Vec<String>> = self.labels.as_ref().map(|_| {
+            useful.iter().map(|s| self.state_label(s)).collect()
+        });
+
+        DFA::from_table(state_map.len(), self.alphabet.clone(), table,
+                        state_map.get_copy(&self.start), accept, labels)
+    }
+
+    /// Returns self with its states named by labels, in order.
+    ///
+    /// Names are used when printing self and in traces. States without a label,
+    /// such as a dead state added by `complete`, are shown by number.
+    pub fn with_labels(mut self, labels: Vec<String>) -> DFA<S> {
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Returns the name of state, or its number if it has no name.
+    pub fn state_label(&self, state: uint) -> String {
+        match self.labels {
+            Some(ref labels) if state < labels.len() => labels.get(state).clone(),
+            _ => state.to_string()
+        }
+    }
+
+    /// Returns the number of the state with the given name.
+    pub fn find_state(&self, label: &str) -> Option<uint> {
+        match self.labels {
+            Some(ref labels) => labels.iter().position(|l| l.as_slice() == label),
+            None => None
+        }
+    }
+
+    /// Returns the names of the states self passes through while reading input,
+    /// starting with the start state.
+    ///
+    /// Returns None if input contains a symbol not in the alphabet. The trace
+    /// stops early if a partial DFA enters its implicit dead state.
+    pub fn trace<I: Word<S>>(&self, input: I) -> Option<Vec<String>> {
+        let symbols = input.symbols();
+        if symbols.iter().any(|sym| !self.alphabet.contains(sym)) {
+            return None;
+        }
+
+        let mut curr_state = self.start;
+        let mut trace = vec!(self.state_label(curr_state));
+        for sym in symbols.iter() {
+            match self.next_state(curr_state, sym) {
+                Some(next) => curr_state = next,
+                None => break
+            }
+
+            trace.push(self.state_label(curr_state));
+        }
+
+        Some(trace)
+    }
+
+    /// Returns the number of states.
+    pub fn num_states(&self) -> uint {
+        self.num_states
+    }
+
+    /// Returns the start state.
+    pub fn start_state(&self) -> uint {
+        self.start
+    }
+
+    /// Returns true if state is an accept state.
+    pub fn is_accept_state(&self, state: uint) -> bool {
+        self.accept.contains(&state)
+    }
+
+    /// Returns the state self moves to from state on sym,
+    /// or None if there is no such transition.
+    pub fn next_state(&self, state: uint, sym: &S) -> Option<uint> {
+        self.column(sym).and_then(|col| self.next(state, col))
+    }
+
+    /// Returns a DFA over alphabet that reads each symbol t as self would read h(t).
+    ///
+    /// It accepts a string exactly when mapping each of its symbols with h gives a string
+    /// that self accepts. Symbols that h maps to None have no transitions, so the result
+    /// may be partial.
+    pub fn inverse_image<T: Symbol>(&self, alphabet: &Vec<T>, h: |&T| -> Option<S>) -> DFA<T> {
+        let (width, old_width) = (alphabet.len(), self.alphabet.len());
+        let mut table = Vec::from_elem(self.num_states * width, DEAD);
+        for (col, t) in alphabet.iter().enumerate() {
+            let old_col = match h(t).and_then(|sym| self.column(&sym)) {
+                Some(old_col) => old_col,
+                None => continue
+            };
+
+            for i in range(0, self.num_states) {
+                *table.get_mut(i * width + col) = *self.table.get(i * old_width + old_col);
+            }
+        }
+
+        DFA::from_table(self.num_states, alphabet.clone(), table, self.start,
+                        self.accept.clone(), self.labels.clone())
+    }
+
+    /// Returns an NFA with the same states and transitions as self.
+    ///
+    /// Missing transitions of a partial DFA are simply missing from the NFA.
+    pub fn to_nfa(&self) -> NFA<S> {
+        let transitions: Vec<Transition<S>> = self.transitions().iter().map(|&(curr, col, next)| {
+            (curr, self.alphabet.get(col).clone(), next)
+        }).collect();
+        let accept: Vec<uint> = self.accept.iter().collect();
+
+        //The states and symbols are all valid already
+        let nfa = NFA::with_epsilons(self.num_states, &self.alphabet, &transitions, &vec!(),
+                                     self.start, &accept).ok().unwrap();
+        match self.labels {
+            Some(ref labels) => nfa.with_labels(labels.clone()),
+            None => nfa
+        }
+    }
+
     /// Return a new DFA recognizing the union of the two inputs.  
     /// The union accepts any string that either input DFA would accept. 
     ///
     /// Returns None if the DFAs do not use the same alphabet.
-    pub fn union (&self, d2: &DFA) -> Option<DFA> {
+    pub fn union (&self, d2: &DFA<S>) -> Option<DFA<S>> {
         DFA::dfa_product(self, d2, |x, y| { x || y })
     }
 
@@ -120,23 +473,106 @@ impl DFA {
     /// Accepts all strings accepted by both input DFAs.
     ///
     /// Returns None if the DFAs do not use the same alphabet.
-    pub fn intersect(&self, d2: &DFA) -> Option<DFA> {
+    pub fn intersect(&self, d2: &DFA<S>) -> Option<DFA<S>> {
         DFA::dfa_product(self, d2, |x, y| { x && y })
     }
 
+    /// Return a new DFA recognizing the union of the two inputs, even if their alphabets differ.
+    ///
+    /// Both DFAs are first extended to the union of their alphabets with `extend_alphabet`.
+    pub fn union_aligned(&self, d2: &DFA<S>) -> DFA<S> {
+        DFA::aligned_product(self, d2, |x, y| { x || y })
+    }
+
+    /// Return a DFA representing the intersection of the inputs, even if their alphabets differ.
+    ///
+    /// Both DFAs are first extended to the union of their alphabets with `extend_alphabet`.
+    pub fn intersect_aligned(&self, d2: &DFA<S>) -> DFA<S> {
+        DFA::aligned_product(self, d2, |x, y| { x && y })
+    }
+
+    /// Return a DFA accepting the strings accepted by self but not by d2.
+    ///
+    /// Both DFAs are first extended to the union of their alphabets with `extend_alphabet`.
+    pub fn difference(&self, d2: &DFA<S>) -> DFA<S> {
+        DFA::aligned_product(self, d2, |x, y| { x && !y })
+    }
+
+    /// Return a DFA accepting the strings accepted by exactly one of self and d2.
+    ///
+    /// Both DFAs are first extended to the union of their alphabets with `extend_alphabet`.
+    pub fn symmetric_difference(&self, d2: &DFA<S>) -> DFA<S> {
+        DFA::aligned_product(self, d2, |x, y| { x != y })
+    }
+
+    /// Return a DFA accepting the strings accepted by both or neither of self and d2.
+    ///
+    /// Both DFAs are first extended to the union of their alphabets with `extend_alphabet`.
+    pub fn xnor(&self, d2: &DFA<S>) -> DFA<S> {
+        DFA::aligned_product(self, d2, |x, y| { x == y })
+    }
+
+    /// Return the product of self and d2, where a string is accepted if
+    /// `op(self accepts, d2 accepts)` is true.
+    ///
+    /// This generalizes union, intersection and the other boolean operations.
+    /// Both DFAs are first extended to the union of their alphabets with `extend_alphabet`.
+    pub fn product_with(&self, d2: &DFA<S>, op: |bool, bool| -> bool) -> DFA<S> {
+        DFA::aligned_product(self, d2, op)
+    }
+
+    /// Returns a DFA over self's alphabet plus symbols, recognizing the same language.
+    ///
+    /// A dead state is added, and every transition on a new symbol leads to it.
+    /// If symbols adds nothing to the alphabet, this is just a copy of self.
+    pub fn extend_alphabet(&self, symbols: &Vec<S>) -> DFA<S> {
+        let mut alphabet = self.alphabet.clone();
+        for sym in symbols.iter() {
+            if !alphabet.contains(sym) {
+                alphabet.push(sym.clone());
+            }
+        }
+
+        if alphabet.len() == self.alphabet.len() {
+            return self.clone();
+        }
+
+        //New symbols are new columns at the end of each row
+        let dead = self.num_states as u32;
+        let (width, old_width) = (alphabet.len(), self.alphabet.len());
+        let mut table = Vec::with_capacity((self.num_states + 1) * width);
+        for i in range(0, self.num_states) {
+            table.push_all(self.table.slice(i * old_width, (i + 1) * old_width));
+            table.grow(width - old_width, &dead);
+        }
+
+        table.grow(width, &dead);
+        DFA::from_table(self.num_states + 1, alphabet, table, self.start,
+                        self.accept.clone(), self.labels.clone())
+    }
+
+    //dfa_product after extending both DFAs to the union of their alphabets
+    fn aligned_product(d1: &DFA<S>, d2: &DFA<S>, f: |bool, bool| -> bool) -> DFA<S> {
+        let e1 = d1.extend_alphabet(&d2.alphabet);
+        let e2 = d2.extend_alphabet(&d1.alphabet);
+
+        //Both alphabets are now the same set of symbols
+        DFA::dfa_product(&e1, &e2, f).unwrap()
+    }
+
     //Take the cartesian product of 2 DFAs.
     //This is the basis for both union and intersection.
-    fn dfa_product(d1: &DFA, d2: &DFA, f: |bool, bool| -> bool) -> Option<DFA> {
+    fn dfa_product(d1: &DFA<S>, d2: &DFA<S>, f: |bool, bool| -> bool) -> Option<DFA<S>> {
         //Check that the DFAs have matching alphabets
-        //To do this, we need to clone and sort :(
-        let mut a1 = d1.alphabet.clone();
-        let mut a2 = d2.alphabet.clone();
-        a1.sort();
-        a2.sort();
-        if a1 != a2 {
+        if !d1.same_alphabet(d2) {
             return None
         }
 
+        //Missing transitions would leave product states without successors
+        if !d1.is_complete() || !d2.is_complete() {
+            return DFA::dfa_product(&d1.complete(), &d2.complete(), f);
+        }
+
         let num_states = d1.num_states * d2.num_states;
         let mut state_map = HashMap::with_capacity(num_states);
         let mut count: uint = 0;
@@ -157,269 +593,1439 @@ impl DFA {
 
         let start: uint = state_map.get_copy(&(d1.start, d2.start));
 
-        //Build the transitions
-        let trns_size = num_states * d1.alphabet.len();
-        let mut trns_fn = HashMap::with_capacity(trns_size);
+        //The alphabets may list the same symbols in different orders
+        let d2_columns: Vec<uint> = d1.alphabet.iter().map(|sym| d2.column(sym).unwrap()).collect();
 
+        //Build the transitions. Rows are added in the order the states were numbered.
+        let mut table = Vec::with_capacity(num_states * d1.alphabet.len());
         for i in range(0, d1.num_states) {
             for j in range(0, d2.num_states) {
-                for sym in d1.alphabet.iter() {
-                    let s1 = d1.delta.get_copy(&(i, *sym));
-                    let s2 = d2.delta.get_copy(&(j, *sym));
-                    let curr_s = state_map.get_copy(&(i,j));
-                    let new_s = state_map.get_copy(&(s1, s2));
-                    trns_fn.insert((curr_s, sym.clone()), new_s);
+                for (col, &d2_col) in d2_columns.iter().enumerate() {
+                    let s1 = d1.next(i, col).unwrap();
+                    let s2 = d2.next(j, d2_col).unwrap();
+                    table.push(state_map.get_copy(&(s1, s2)) as u32);
                 }
             }
         }
 
-        Some(DFA {accept: accept,
-                  start: start,
-                  delta: trns_fn,
-                  alphabet: d1.alphabet.clone(),
-                  num_states: num_states})
+        Some(DFA::from_table(num_states, d1.alphabet.clone(), table, start, accept, None))
     }
 
     /// Returns a DFA accepting the complement of self. 
     ///
     /// It accepts all strings over self's alphabet that self rejects and vice versa.
-    pub fn complement(&self) -> DFA {
-        let all_states: Vec<uint> = range(0, self.num_states).collect();
-        let accept: Vec<uint> = all_states.move_iter().filter(|x| !self.accept.contains(x)).collect();
+    pub fn complement(&self) -> DFA<S> {
+        //The implicit dead state of a partial DFA must become accepting too
+        let mut dfa = self.complete();
+        let all_states: Vec<uint> = range(0, dfa.num_states).collect();
+        let accept: Vec<uint> = all_states.move_iter().filter(|x| !dfa.accept.contains(x)).collect();
         
         let mut bv = BitvSet::new();
         for i in accept.iter() {
             bv.insert(*i);
         }
 
-        DFA { accept: bv,
-              start: self.start,
-              alphabet: self.alphabet.clone(),
-              delta: self.delta.clone(),
-              num_states: self.num_states
-        }
+        dfa.accept = bv;
+        dfa
     }
 
     //Returns a BitvSet containing all the states that are reachable by some path from the
     //start state 
     fn reachable_states(&self) -> BitvSet {
+        //Each state is pushed at most once, so this takes O(n k) time
+        let mut visited = Vec::from_elem(self.num_states, false);
+        *visited.get_mut(self.start) = true;
+        let mut stack = vec!(self.start);
+
+        loop {
+            let curr = match stack.pop() {
+                Some(s) => s,
+                None => break
+            };
+
+            for col in range(0, self.alphabet.len()) {
+                match self.next(curr, col) {
+                    Some(next) if !*visited.get(next) => {
+                        *visited.get_mut(next) = true;
+                        stack.push(next);
+                    },
+                    _ => {}
+                }
+            }
+        }
+
         let mut reachable = BitvSet::new();
-        reachable.insert(self.start);
-        let mut new_states = BitvSet::new();
-        new_states.insert(self.start);
+        for (s, &v) in visited.iter().enumerate() {
+            if v {
+                reachable.insert(s);
+            }
+        }
+
+        return reachable;
+    }
 
+    //Returns a BitvSet containing all the states from which some accept state is reachable
+    fn coreachable_states(&self) -> BitvSet {
+        let mut coreachable = BitvSet::new();
+        for i in self.accept.iter() {
+            if i < self.num_states {
+                coreachable.insert(i);
+            }
+        }
+
+        let transitions = self.transitions();
         loop {
             let mut temp = BitvSet::new();
-            for elem in new_states.iter() {
-                for sym in self.alphabet.iter() {
-                    temp.insert(self.delta.get_copy(&(elem, *sym)));
+            for &(curr, _, next) in transitions.iter() {
+                if coreachable.contains(&next) && !coreachable.contains(&curr) {
+                    temp.insert(curr);
                 }
             }
 
-            temp.difference_with(&reachable);
-            reachable.union_with(&temp);
-            new_states = temp;
-
-            if new_states.is_empty() {
+            if temp.is_empty() {
                 break;
             }
+
+            coreachable.union_with(&temp);
         }
 
-        return reachable;
+        return coreachable;
     }
 
-    //Split states into partitions using Hopcroft's algorithm.
-    fn partition_states(&self) -> Vec<BitvSet> {
-        //Remove unreachable states
-        let reachable = self.reachable_states();
+    //Splits the states into blocks of equivalent states using Hopcroft's algorithm.
+    //self must be complete. Unreachable states are partitioned as well.
+    //
+    //Starting from the accept and non-accept states, blocks are split until no block has
+    //states that lead into some block on a symbol alongside states that do not. The worklist
+    //holds (block, column) pairs whose predecessors have not been used to split yet. When a block
+    //is split while it is waiting, both halves must wait; otherwise only the smaller one needs to,
+    //so each state enters the worklist O(log n) times per symbol and the whole takes O(n k log n).
+    fn partition_states(&self) -> Partition {
+        let width = self.alphabet.len();
+
+        //The states leading to each state on each symbol, as runs of inverse
+        //indexed by inverse_start.get(state * width + col)
+        let transitions = self.transitions();
+        let mut inverse_start = Vec::from_elem(self.num_states * width + 1, 0u);
+        for &(_, col, next) in transitions.iter() {
+            *inverse_start.get_mut(next * width + col + 1) += 1;
+        }
+
+        for i in range(1, inverse_start.len()) {
+            let prev = *inverse_start.get(i - 1);
+            *inverse_start.get_mut(i) += prev;
+        }
 
-        //Minimize with Hopcroft's
-        let mut partitions = vec!();
-        let mut w = vec!();
+        let mut inverse = Vec::from_elem(self.num_states * width, 0u);
+        let mut fill = inverse_start.clone();
+        for &(curr, col, next) in transitions.iter() {
+            let pos = fill.get_mut(next * width + col);
+            *inverse.get_mut(*pos) = curr;
+            *pos += 1;
+        }
 
-        let mut non_accept = reachable.clone();
-        non_accept.difference_with(&self.accept);
+        let mut partition = Partition::new(self.num_states);
+        for s in self.accept.iter() {
+            partition.mark(s);
+        }
 
-        let mut reachable_accept = self.accept.clone();
-        reachable_accept.intersect_with(&reachable);
+        //If every state accepts, or none do, there is only one block and nothing to split it
+        let mut worklist = vec!();
+        for &(rest, accept) in partition.split().iter() {
+            let smaller = if partition.size(accept) <= partition.size(rest) { accept } else { rest };
+            for col in range(0, width) {
+                worklist.push((smaller, col));
+            }
+        }
 
-        partitions.push(reachable_accept.clone());
-        partitions.push(non_accept);
-        w.push(reachable_accept);
+        //waiting.get(block * width + col) is true while (block, col) is in the worklist
+        let mut waiting = Vec::from_elem(partition.num_blocks() * width, false);
+        for &(block, col) in worklist.iter() {
+            *waiting.get_mut(block * width + col) = true;
+        }
 
-        //Loop until w is empty
         loop {
-            let set = match w.pop() {
-                Some(s) => s,
+            let (splitter, col) = match worklist.pop() {
+                Some(pair) => pair,
                 None => break
             };
 
-            for sym in self.alphabet.iter() {
-                let mut x = BitvSet::new();
-                for s in reachable.iter() {
-                    match self.delta.find(&(s, *sym)) {
-                        Some(v) if set.contains(v) => { x.insert(*v); },
-                        _ => {}
-                    }
-                }
+            *waiting.get_mut(splitter * width + col) = false;
 
-                let mut new_p = vec!();
-
-                for y in partitions.move_iter() {
-                    let mut intersection = y.clone();
-                    intersection.intersect_with(&x);
-                    if intersection.is_empty() {
-                        new_p.push(y);
-                        continue;
-                    }
+            //Marking moves states around within their blocks, so copy the splitter first
+            let states = partition.states(splitter).to_vec();
+            for &s in states.iter() {
+                let idx = s * width + col;
+                for &pred in inverse.slice(*inverse_start.get(idx), *inverse_start.get(idx + 1)).iter() {
+                    partition.mark(pred);
+                }
+            }
 
-                    let mut difference = y.clone();
-                    difference.difference_with(&x);
-                    if difference.is_empty() {
-                        new_p.push(y);
-                        continue;
+            for &(old, new) in partition.split().iter() {
+                waiting.grow(width, &false);
+                for c in range(0, width) {
+                    let target = if *waiting.get(old * width + c) {
+                        new
                     }
 
-                    new_p.push(intersection.clone());
-                    new_p.push(difference.clone());
-
-                    if w.contains(&y) {
-                        w.push(intersection);
-                        w.push(difference);
+                    else if partition.size(new) <= partition.size(old) {
+                        new
                     }
 
                     else {
-                        if intersection.len() <= difference.len() {
-                            w.push(intersection);
-                        }
+                        old
+                    };
 
-                        else {
-                            w.push(difference);
-                        }
-                    }
+                    *waiting.get_mut(target * width + c) = true;
+                    worklist.push((target, c));
                 }
-
-                partitions = new_p;
             }
         }
 
-        //Remove empty partitions and return
-       partitions.move_iter().filter(|ref x| !x.is_empty()).collect()
+        partition
     }
 
     /// Reduces the amount of states in-place to the minimum necessary to recognize the same language.
     /// 
-    /// Implements [Hopcroft's algorithm](http://en.wikipedia.org/wiki/DFA_minimization#Hopcroft.27s_algorithm).
+    /// Implements [Hopcroft's algorithm](http://en.wikipedia.org/wiki/DFA_minimization#Hopcroft.27s_algorithm),
+    /// which takes O(n k log n) time for n states and k symbols. Unreachable states are removed,
+    /// and the remaining states are numbered in the order their first original state appears.
     pub fn minimize(&mut self) {
-        let partitions = self.partition_states();
-        //partitions now holds all the equivalence classes
-        //Build a new transition function with 1 state for each set in partitions
-        //Use the index of the set as its state number
-        let mut transitions = HashMap::new();
-        let mut accept = BitvSet::new();
-        for (idx, p) in partitions.iter().enumerate() {
-            //get first element of p
-            let elem = match p.iter().next() {
-                Some(e) => e,
-                None => unreachable!() //There should be no empty partitions
-            };
+        //The implicit dead state of a partial DFA takes part in the partitioning
+        if !self.is_complete() {
+            *self = self.complete();
+        }
 
-            for sym in self.alphabet.iter() {
-                let new_state = self.delta.get(&(elem, *sym));
-                for (new_idx, s) in partitions.iter().enumerate() {
-                    if s.contains(new_state) {
-                        transitions.insert((idx, *sym), new_idx);//(idx, *sym, new_idx));
-                        break;
-                    }
-                }
-            }
+        let partition = self.partition_states();
+        let blocks: Vec<uint> = range(0, self.num_states).map(|s| partition.block_of(s)).collect();
+        self.merge_classes(&blocks);
+    }
+
+    /// Reduces the amount of states in-place to the minimum necessary to recognize the same language,
+    /// using Moore's algorithm.
+    ///
+    /// The reachable states start out split into accept and non-accept states. In each round,
+    /// two states stay in the same class only if they were in the same class and lead to states
+    /// in the same class on every symbol. Once a round splits no class, the classes are the states
+    /// of the minimal DFA. This takes O(n^2 k) time for n states and k symbols, so `minimize` is
+    /// faster, but each round is easy to follow. The result is numbered the same way as by `minimize`.
+    pub fn minimize_moore(&mut self) {
+        if !self.is_complete() {
+            *self = self.complete();
+        }
 
-            if p.contains(&self.start) {
-                self.start = idx;
+        let states: Vec<uint> = self.reachable_states().iter().collect();
+        let mut class = Vec::from_elem(self.num_states, 0u);
+        for &s in states.iter() {
+            if self.accept.contains(&s) {
+                *class.get_mut(s) = 1;
             }
+        }
 
-            for i in self.accept.iter() {
-                if p.contains(&i) {
-                    accept.insert(idx);
-                    break;
+        let has_accept = states.iter().any(|s| self.accept.contains(s));
+        let has_reject = states.iter().any(|s| !self.accept.contains(s));
+        let mut num_classes = if has_accept && has_reject { 2 } else { 1 };
+
+        loop {
+            //States whose class and successors' classes match share a class in the next round
+            let mut signatures = HashMap::new();
+            let mut next_class = Vec::from_elem(self.num_states, 0u);
+            for &s in states.iter() {
+                let mut signature = vec!(*class.get(s));
+                for col in range(0, self.alphabet.len()) {
+                    signature.push(*class.get(self.next(s, col).unwrap()));
                 }
+
+                let fresh = signatures.len();
+                *next_class.get_mut(s) = *signatures.find_or_insert(signature, fresh);
             }
-        }
 
-        self.delta = transitions;
-        self.accept = accept;
-        self.num_states = partitions.len();
-        assert_eq!(self.delta.len(), self.alphabet.len() * partitions.len());
-    }
+            class = next_class;
+            if signatures.len() == num_classes {
+                break;
+            }
 
-    /// Return true if there are no reachable accept states
-    fn accepts_none(&self) -> bool {
-        if self.accept.is_empty() {
-            return true;
+            num_classes = signatures.len();
         }
 
-        let mut reachable = self.reachable_states();
-        reachable.intersect_with(&self.accept);
-        return reachable.is_empty();
+        self.merge_classes(&class);
     }
-}
 
-impl Run for DFA {
-    fn run(&self, string: &str) -> Option<bool> {
-        let mut curr_state = self.start;
-
-        // Compute the transition for each char in string
-        for sym in string.chars() { 
-             match self.delta.find_copy(&(curr_state, sym)) {
-                Some(v) => curr_state = v,
-                None => return None
-             }
-        }
+    /// Reduces the amount of states in-place to the minimum necessary to recognize the same language,
+    /// using Brzozowski's algorithm.
+    ///
+    /// self is reversed and determinized twice. Determinizing the reverse of a DFA whose states are
+    /// all reachable gives a DFA whose states all accept different languages, so the second round
+    /// leaves the minimal DFA. Each subset construction can take exponential time, but usually does not.
+    /// The result has the same states as with `minimize`, possibly numbered differently.
+    pub fn minimize_brzozowski(&mut self) {
+        *self = self.reverse_determinize().reverse_determinize();
+    }
 
-        Some(self.accept.contains(&curr_state)) 
+    //Determinizes the reverse of self with the subset construction. Starting from the set of accept
+    //states, rather than the reversed NFA's new start state, keeps that state out of every subset.
+    fn reverse_determinize(&self) -> DFA<S> {
+        let accept: Vec<uint> = self.accept.iter().collect();
+        self.to_nfa().reverse().to_dfa_from(&accept)
     }
-}
 
-/// **Note**: Tests for equality in terms on the language recognized, not the actual states/transitions
-/// in the DFA.
-impl PartialEq for DFA {
-    /// Check that (self intersect ~other) union (~self intersect other)
-    /// accepts the empty language 
-    fn eq(&self, other: &DFA) -> bool {
-        let eq1 = match self.intersect(&other.complement()) {
-            None => { return false },
-            Some(dfa) => dfa
-        };
+    //Replaces self with its quotient by the given classes of reachable states, where class.get(s)
+    //is the class of state s. Every class must be below num_states, and states in the same class
+    //must be equivalent. The new states are numbered in the order their first original state appears.
+    fn merge_classes(&mut self, class: &Vec<uint>) {
+        //Any state of a class can stand for the whole class
+        let mut new_state = Vec::from_elem(self.num_states, None);
+        let mut reps = vec!();
+        for s in self.reachable_states().iter() {
+            let c = *class.get(s);
+            if (*new_state.get(c)).is_none() {
+                *new_state.get_mut(c) = Some(reps.len());
+                reps.push(s);
+            }
+        }
+
+        let width = self.alphabet.len();
+        let mut table = Vec::with_capacity(reps.len() * width);
+        let mut accept = BitvSet::new();
+        for (idx, &rep) in reps.iter().enumerate() {
+            for col in range(0, width) {
+                //Every state reachable from a reachable state is reachable
+                let next = self.next(rep, col).unwrap();
+                table.push((*new_state.get(*class.get(next))).unwrap() as u32);
+            }
+
+            if self.accept.contains(&rep) {
+                accept.insert(idx);
+            }
+        }
+
+        self.start = (*new_state.get(*class.get(self.start))).unwrap();
+        self.table = table;
+        self.accept = accept;
+        self.num_states = reps.len();
+        self.labels = None;
+    }
+
+    /// Checks if self and other recognize the same language.
+    ///
+    /// Implements the near-linear algorithm of
+    /// [Hopcroft and Karp](http://ecommons.cornell.edu/handle/1813/5958), working directly
+    /// on both transition functions instead of building product DFAs.
+    /// The states of both DFAs are kept in a union-find structure, starting with the two start
+    /// states merged. Whenever two states are merged, their successors on each symbol must
+    /// be merged as well. The DFAs are equivalent if no merged pair of states disagrees on
+    /// acceptance.
+    ///
+    /// If the alphabets differ, both DFAs are compared as if extended to the union
+    /// of their alphabets with `extend_alphabet`. Use `equivalent_strict` to consider
+    /// DFAs over different alphabets unequal.
+    pub fn equivalent(&self, other: &DFA<S>) -> bool {
+        if self.same_alphabet(other) {
+            self.equivalent_same_alphabet(other)
+        }
+
+        else {
+            let e1 = self.extend_alphabet(&other.alphabet);
+            let e2 = other.extend_alphabet(&self.alphabet);
+            e1.equivalent_same_alphabet(&e2)
+        }
+    }
+
+    /// Checks if self and other use the same alphabet and recognize the same language.
+    pub fn equivalent_strict(&self, other: &DFA<S>) -> bool {
+        self.same_alphabet(other) && self.equivalent_same_alphabet(other)
+    }
+
+    //Check if both alphabets contain the same symbols, in any order
+    fn same_alphabet(&self, other: &DFA<S>) -> bool {
+        let mut a1 = self.alphabet.clone();
+        let mut a2 = other.alphabet.clone();
+        a1.sort();
+        a2.sort();
+        a1 == a2
+    }
+
+    //Hopcroft-Karp on two DFAs that share an alphabet
+    fn equivalent_same_alphabet(&self, other: &DFA<S>) -> bool {
+        if !self.is_complete() || !other.is_complete() {
+            return self.complete().equivalent_same_alphabet(&other.complete());
+        }
+
+        //States of other are numbered after the states of self
+        let offset = self.num_states;
+        let mut sets = UnionFind::new(self.num_states + other.num_states);
+        let mut pending = vec!();
+
+        if self.accept.contains(&self.start) != other.accept.contains(&other.start) {
+            return false;
+        }
+
+        sets.union(self.start, other.start + offset);
+        pending.push((self.start, other.start));
+
+        //The alphabets may list the same symbols in different orders
+        let other_columns: Vec<uint> = self.alphabet.iter().map(|sym| other.column(sym).unwrap()).collect();
+
+        loop {
+            let (p, q) = match pending.pop() {
+                Some(pair) => pair,
+                None => return true
+            };
+
+            for (col, &other_col) in other_columns.iter().enumerate() {
+                let p_next = self.next(p, col).unwrap();
+                let q_next = other.next(q, other_col).unwrap();
+
+                if sets.union(p_next, q_next + offset) {
+                    if self.accept.contains(&p_next) != other.accept.contains(&q_next) {
+                        return false;
+                    }
+
+                    pending.push((p_next, q_next));
+                }
+            }
+        }
+    }
+
+    //Searches the product of self and other, over the union of their alphabets, for the shortest
+    //string where f(self accepts, other accepts) is true. Of the shortest strings, the
+    //lexicographically first is found.
+    fn product_witness(&self, other: &DFA<S>, f: |bool, bool| -> bool) -> Option<Vec<S>> {
+        DFA::aligned_product(self, other, f).shortest_accepted()
+    }
+
+    //Returns the first string self accepts in shortlex order, or None if there is none.
+    //A breadth first search records the state and column each state was first reached from.
+    fn shortest_accepted(&self) -> Option<Vec<S>> {
+        let mut parent: Vec<Option<(uint, uint)>> = Vec::from_elem(self.num_states, None);
+        let mut visited = Vec::from_elem(self.num_states, false);
+        *visited.get_mut(self.start) = true;
+        let mut queue = RingBuf::new();
+        queue.push_back(self.start);
+
+        loop {
+            let curr = match queue.pop_front() {
+                Some(s) => s,
+                None => return None
+            };
+
+            if self.accept.contains(&curr) {
+                //Walk back to the start to recover the string
+                let mut syms = vec!();
+                let mut s = curr;
+                loop {
+                    match *parent.get(s) {
+                        Some((prev, col)) => {
+                            syms.push(self.alphabet.get(col).clone());
+                            s = prev;
+                        },
+                        None => break
+                    }
+                }
+
+                syms.reverse();
+                return Some(syms);
+            }
+
+            //Columns are sorted by symbol, so each state is first reached by its shortlex first string
+            for &(_, col) in self.columns.iter() {
+                match self.next(curr, col) {
+                    Some(next) if !*visited.get(next) => {
+                        *visited.get_mut(next) = true;
+                        *parent.get_mut(next) = Some((curr, col));
+                        queue.push_back(next);
+                    },
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Returns the number of strings of exactly length symbols that self accepts.
+    ///
+    /// Counts the paths of each length from the start state by dynamic programming
+    /// over the transition table, taking O(length * states * symbols) additions.
+    pub fn count_accepted(&self, length: uint) -> BigUint {
+        let mut counts = self.paths_from_start();
+        for _ in range(0, length) {
+            counts = self.extend_paths(&counts);
+        }
+
+        self.sum_accepted(&counts)
+    }
+
+    /// Returns the number of strings of at most length symbols that self accepts.
+    pub fn count_accepted_up_to(&self, length: uint) -> BigUint {
+        let mut counts = self.paths_from_start();
+        let mut total = self.sum_accepted(&counts);
+        for _ in range(0, length) {
+            counts = self.extend_paths(&counts);
+            total = total.add(&self.sum_accepted(&counts));
+        }
+
+        total
+    }
+
+    /// Returns the generating function of the language, whose coefficient of x^n
+    /// is the number of accepted strings of length n.
+    ///
+    /// The generating function of a regular language is always rational. It is computed as
+    /// P(x) / Q(x), where Q(x) = det(I - xA) for the transition count matrix A of the states
+    /// that are both reachable and can reach an accept state, and P(x) is found from the first
+    /// few counts. The characteristic polynomial is found with the Faddeev-LeVerrier algorithm,
+    /// which takes O(n^4) big integer operations for n such states.
+    /// The fraction is not necessarily in lowest terms.
+    pub fn generating_function(&self) -> GeneratingFunction {
+        let mut useful = self.reachable_states();
+        useful.intersect_with(&self.coreachable_states());
+
+        if !useful.contains(&self.start) {
+            return GeneratingFunction { numerator: vec!(), denominator: vec!(One::one()) };
+        }
+
+        let states: Vec<uint> = useful.iter().collect();
+        let n = states.len();
+        let mut index = HashMap::with_capacity(n);
+        for (i, &state) in states.iter().enumerate() {
+            index.insert(state, i);
+        }
+
+        //a.get(i).get(j) is the number of symbols leading from state i to state j
+        let mut a: Vec<Vec<BigInt>> = Vec::from_fn(n, |_| Vec::from_elem(n, Zero::zero()));
+        for &(curr, _, next) in self.transitions().iter() {
+            match (index.find_copy(&curr), index.find_copy(&next)) {
+                (Some(i), Some(j)) => {
+                    let entry = a.get_mut(i).get_mut(j);
+                    *entry = entry.add(&One::one());
+                },
+                _ => {}
+            }
+        }
+
+        //Faddeev-LeVerrier: c is the characteristic polynomial det(tI - A), highest degree first
+        let mut c: Vec<BigInt> = vec!(One::one());
+        let mut m: Vec<Vec<BigInt>> = Vec::from_fn(n, |_| Vec::from_elem(n, Zero::zero()));
+        for k in range(1, n + 1) {
+            m = mat_mul(&a, &m);
+            for i in range(0, n) {
+                let entry = m.get_mut(i).get_mut(i);
+                *entry = entry.add(c.get(k - 1));
+            }
+
+            let am = mat_mul(&a, &m);
+            let mut trace: BigInt = Zero::zero();
+            for i in range(0, n) {
+                trace = trace.add(am.get(i).get(i));
+            }
+
+            let k_big: BigInt = FromPrimitive::from_uint(k).unwrap();
+            c.push(trace.div(&k_big).neg());
+        }
+
+        //det(I - xA) has the same coefficients as det(tI - A), lowest degree first
+        let denominator = c;
+
+        //The numerator has degree below n, so it is fixed by the first n counts
+        let mut counts = self.paths_from_start();
+        let mut series: Vec<BigInt> = vec!();
+        for _ in range(0, n) {
+            series.push(BigInt::from_biguint(Plus, self.sum_accepted(&counts)));
+            counts = self.extend_paths(&counts);
+        }
+
+        let mut numerator = vec!();
+        for j in range(0, n) {
+            let mut coeff: BigInt = Zero::zero();
+            for i in range(0, j + 1) {
+                coeff = coeff.add(&denominator.get(i).mul(series.get(j - i)));
+            }
+
+            numerator.push(coeff);
+        }
+
+        GeneratingFunction {
+            numerator: trim_zeros(numerator),
+            denominator: trim_zeros(denominator)
+        }
+    }
+
+    //Number of paths of length 0 from the start to each state
+    fn paths_from_start(&self) -> Vec<BigUint> {
+        let mut counts = Vec::from_elem(self.num_states, Zero::zero());
+        *counts.get_mut(self.start) = One::one();
+        counts
+    }
+
+    //Given the number of paths of some length to each state, count the paths one symbol longer
+    fn extend_paths(&self, counts: &Vec<BigUint>) -> Vec<BigUint> {
+        let mut next: Vec<BigUint> = Vec::from_elem(self.num_states, Zero::zero());
+        for &(curr, _, to) in self.transitions().iter() {
+            if counts.get(curr).is_zero() {
+                continue;
+            }
+
+            let entry = next.get_mut(to);
+            *entry = entry.add(counts.get(curr));
+        }
+
+        next
+    }
+
+    //Total paths ending in an accept state
+    fn sum_accepted(&self, counts: &Vec<BigUint>) -> BigUint {
+        let mut total: BigUint = Zero::zero();
+        for i in self.accept.iter() {
+            if i < self.num_states {
+                total = total.add(counts.get(i));
+            }
+        }
+
+        total
+    }
+
+    /// Return true if there are no reachable accept states, so self accepts no strings.
+    pub fn is_empty(&self) -> bool {
+        if self.accept.is_empty() {
+            return true;
+        }
+
+        let mut reachable = self.reachable_states();
+        reachable.intersect_with(&self.accept);
+        return reachable.is_empty();
+    }
+
+    /// Return true if self accepts every string over its alphabet.
+    pub fn is_universal(&self) -> bool {
+        self.complement().is_empty()
+    }
+
+    /// Return true if self accepts only finitely many strings.
+    ///
+    /// The language is infinite exactly when a cycle passes through states that are reachable
+    /// from the start state and can reach an accept state.
+    pub fn is_finite(&self) -> bool {
+        match self.longest_path() {
+            Unbounded => false,
+            _ => true
+        }
+    }
+
+    /// Returns the length of the longest string self accepts,
+    /// or None if self accepts no strings or infinitely many.
+    pub fn longest_accepted_length(&self) -> Option<uint> {
+        match self.longest_path() {
+            Finite(len) => Some(len),
+            NoPath | Unbounded => None
+        }
+    }
+
+    /// Returns the shortest string accepted by exactly one of self and other,
+    /// or None if they recognize the same language.
+    ///
+    /// The boolean is true if self is the one that accepts the string.
+    /// If several strings of the shortest length exist, the lexicographically first is returned.
+    ///
+    /// A symbol outside of a DFA's alphabet is treated as leading to a rejecting dead state,
+    /// so DFAs over different alphabets can still be compared.
+    pub fn distinguishing_word(&self, other: &DFA<S>) -> Option<(Vec<S>, bool)> {
+        self.product_witness(other, |x, y| { x != y }).map(|word| {
+            let in_self = self.run(word.as_slice()).unwrap_or(false);
+            (word, in_self)
+        })
+    }
+
+    /// Returns the shortest string accepted by self but not by other,
+    /// or None if every string self accepts is also accepted by other.
+    ///
+    /// Symbols outside of other's alphabet are rejected by other.
+    pub fn subset_witness(&self, other: &DFA<S>) -> Option<Vec<S>> {
+        self.product_witness(other, |x, y| { x && !y })
+    }
+
+    /// Returns an iterator over every string self accepts, in shortlex order.
+    ///
+    /// Strings are ordered by length, and strings of the same length by their symbols.
+    /// They are generated lazily by a breadth first search from the start state that skips
+    /// states from which no accept state can be reached, so the iterator ends if the language is finite.
+    /// Use `take` on the iterator or `first_accepted_words` to bound the number of strings.
+    pub fn accepted_words<'a>(&'a self) -> AcceptedStrings<'a, S> {
+        AcceptedStrings::new(self, None)
+    }
+
+    /// Returns an iterator over every string of at most max_length symbols that self accepts,
+    /// in shortlex order.
+    pub fn accepted_words_up_to<'a>(&'a self, max_length: uint) -> AcceptedStrings<'a, S> {
+        AcceptedStrings::new(self, Some(max_length))
+    }
+
+    /// Returns the first count strings self accepts in shortlex order,
+    /// or all of them if there are fewer.
+    pub fn first_accepted_words(&self, count: uint) -> Vec<Vec<S>> {
+        self.accepted_words().take(count).collect()
+    }
+
+    /// Returns a uniformly random string of exactly length symbols that self accepts,
+    /// or None if self accepts no strings of that length.
+    ///
+    /// To draw many strings, build a `Sampler` once with `sampler` instead.
+    pub fn sample_word<R: Rng>(&self, length: uint, rng: &mut R) -> Option<Vec<S>> {
+        self.sampler(length).sample(length, rng)
+    }
+
+    /// Prepares to draw random accepted strings of up to max_length symbols.
+    ///
+    /// Counts, for every state and every length up to max_length, the number of strings
+    /// that lead from the state to an accept state.
+    pub fn sampler<'a>(&'a self, max_length: uint) -> Sampler<'a, S> {
+        let mut alphabet = self.alphabet.clone();
+        alphabet.sort();
+
+        let mut first = Vec::from_elem(self.num_states, Zero::zero());
+        for i in self.accept.iter() {
+            if i < self.num_states {
+                *first.get_mut(i) = One::one();
+            }
+        }
+
+        let mut suffixes: Vec<Vec<BigUint>> = vec!(first);
+        for len in range(1, max_length + 1) {
+            let prev = suffixes.get(len - 1).clone();
+            let counts = Vec::from_fn(self.num_states, |s| {
+                let mut total: BigUint = Zero::zero();
+                for sym in alphabet.iter() {
+                    match self.next_state(s, sym) {
+                        Some(next) => total = total.add(prev.get(next)),
+                        None => {}
+                    }
+                }
+
+                total
+            });
+
+            suffixes.push(counts);
+        }
+
+        Sampler {
+            dfa: self,
+            alphabet: alphabet,
+            suffixes: suffixes
+        }
+    }
+
+    fn longest_path(&self) -> graph::PathLength {
+        let edges: Vec<(uint, uint, uint)> = self.transitions().iter().map(|&(curr, _, next)| (curr, next, 1u)).collect();
+        graph::longest_path(self.num_states, &edges, self.start, &self.accept)
+    }
+}
+
+//Operations that produce strings or regular expressions, which are made of chars
+impl DFA<char> {
+    /// Returns a regular expression recognizing the same language as self.
+    ///
+    /// Uses state elimination, removing states in the order chosen by the `LeastWeight` heuristic.
+    pub fn to_regex(&self) -> Regex {
+        self.to_regex_with(LeastWeight)
+    }
+
+    /// Returns a regular expression recognizing the same language as self,
+    /// eliminating states in the given order.
+    ///
+    /// The DFA is treated as a generalized NFA whose edges are labelled with regular expressions,
+    /// with a new start state and a single new accept state. States are removed one at a time,
+    /// replacing every path through a removed state with an edge labelled by the equivalent expression.
+    /// When only the new start and accept states are left, the label between them is the result.
+    pub fn to_regex_with(&self, order: EliminationOrder) -> Regex {
+        //Only states on some path from the start to an accept state contribute to the language
+        let mut useful = self.reachable_states();
+        useful.intersect_with(&self.coreachable_states());
+
+        if !useful.contains(&self.start) {
+            return Empty;
+        }
+
+        //Nodes 0..n are the useful states, followed by the new start and accept nodes
+        let states: Vec<uint> = useful.iter().collect();
+        let n = states.len();
+        let (first, last) = (n, n + 1);
+
+        let mut index = HashMap::with_capacity(n);
+        for (i, &state) in states.iter().enumerate() {
+            index.insert(state, i);
+        }
+
+        //edges.get(i).get(j) is the label of the edge from node i to node j
+        let mut edges: Vec<Vec<Regex>> = Vec::from_fn(n + 2, |_| Vec::from_elem(n + 2, Empty));
+
+        for &(curr, col, next) in self.transitions().iter() {
+            match (index.find_copy(&curr), index.find_copy(&next)) {
+                (Some(i), Some(j)) => {
+                    let edge = edges.get_mut(i).get_mut(j);
+                    *edge = Regex::alternate(edge.clone(), Literal(*self.alphabet.get(col)));
+                },
+                _ => {}
+            }
+        }
+
+        *edges.get_mut(first).get_mut(index.get_copy(&self.start)) = Epsilon;
+        for (i, &state) in states.iter().enumerate() {
+            if self.accept.contains(&state) {
+                *edges.get_mut(i).get_mut(last) = Epsilon;
+            }
+        }
+
+        let mut remaining: Vec<uint> = range(0, n).collect();
+        while !remaining.is_empty() {
+            let k = match order {
+                InOrder => remaining.remove(0).unwrap(),
+                _ => {
+                    let pos = cheapest_elimination(&edges, &remaining, order);
+                    remaining.remove(pos).unwrap()
+                }
+            };
+
+            let mut nodes = remaining.clone();
+            nodes.push(first);
+            nodes.push(last);
+
+            //Replace every path i -> k -> j with the edge i -> j
+            let self_loop = Regex::star(edges.get(k).get(k).clone());
+            for &i in nodes.iter() {
+                let into = edges.get(i).get(k).clone();
+                if into == Empty {
+                    continue;
+                }
+
+                for &j in nodes.iter() {
+                    let out = edges.get(k).get(j).clone();
+                    if out == Empty {
+                        continue;
+                    }
+
+                    let path = Regex::concat(Regex::concat(into.clone(), self_loop.clone()), out);
+                    let edge = edges.get_mut(i).get_mut(j);
+                    *edge = Regex::alternate(edge.clone(), path);
+                }
+            }
+
+            //Disconnect k so it is not counted by the heuristics
+            for i in range(0, n + 2) {
+                *edges.get_mut(i).get_mut(k) = Empty;
+                *edges.get_mut(k).get_mut(i) = Empty;
+            }
+        }
+
+        edges.get(first).get(last).simplify()
+    }
+
+    /// Returns the shortest string accepted by exactly one of self and other,
+    /// or None if they recognize the same language.
+    ///
+    /// The boolean is true if self is the one that accepts the string. See `distinguishing_word`.
+    pub fn distinguishing_string(&self, other: &DFA) -> Option<(String, bool)> {
+        self.distinguishing_word(other).map(|(word, in_self)| (word.move_iter().collect(), in_self))
+    }
+
+    /// Checks if every string self accepts is also accepted by other.
+    ///
+    /// Returns an Err holding the shortest string accepted by self but not by other
+    /// if self is not a subset. See `subset_witness`.
+    pub fn is_subset_of(&self, other: &DFA) -> Result<(), String> {
+        match self.subset_witness(other) {
+            None => Ok(()),
+            Some(witness) => Err(witness.move_iter().collect())
+        }
+    }
+
+    /// Returns an iterator over every string self accepts, in shortlex order.
+    /// See `accepted_words`.
+    pub fn accepted_strings<'a>(&'a self) -> Strings<AcceptedStrings<'a, char>> {
+        Strings::new(self.accepted_words())
+    }
 
-        let eq2 = match other.intersect(&self.complement()) {
-            None => { return false },
-            Some(dfa) => dfa
+    /// Returns an iterator over every string of at most max_length symbols that self accepts,
+    /// in shortlex order.
+    pub fn accepted_strings_up_to<'a>(&'a self, max_length: uint) -> Strings<AcceptedStrings<'a, char>> {
+        Strings::new(self.accepted_words_up_to(max_length))
+    }
+
+    /// Returns the first count strings self accepts in shortlex order,
+    /// or all of them if there are fewer.
+    pub fn first_accepted(&self, count: uint) -> Vec<String> {
+        self.accepted_strings().take(count).collect()
+    }
+
+    /// Returns a uniformly random string of exactly length symbols that self accepts,
+    /// or None if self accepts no strings of that length. See `sample_word`.
+    pub fn sample<R: Rng>(&self, length: uint, rng: &mut R) -> Option<String> {
+        self.sample_word(length, rng).map(|word| word.move_iter().collect())
+    }
+}
+
+/// A rational generating function P(x) / Q(x).
+///
+/// Coefficients are stored lowest degree first, without trailing zeros.
+#[deriving(Clone, PartialEq)]
+pub struct GeneratingFunction {
+    /// Coefficients of P(x)
+    pub numerator: Vec<BigInt>,
+    /// Coefficients of Q(x)
+    pub denominator: Vec<BigInt>
+}
+
+impl fmt::Show for GeneratingFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "("));
+        try!(write_polynomial(f, &self.numerator));
+        try!(write!(f, ") / ("));
+        try!(write_polynomial(f, &self.denominator));
+        write!(f, ")")
+    }
+}
+
+//Writes a polynomial such as 1 - 2x + x^2
+fn write_polynomial(f: &mut fmt::Formatter, coeffs: &Vec<BigInt>) -> fmt::Result {
+    let mut first = true;
+    for (k, c) in coeffs.iter().enumerate() {
+        if c.is_zero() {
+            continue;
+        }
+
+        if first {
+            if c.is_negative() {
+                try!(write!(f, "-"));
+            }
+        }
+
+        else {
+            try!(write!(f, "{}", if c.is_negative() { " - " } else { " + " }));
+        }
+
+        let abs = c.abs();
+        if k == 0 || abs != One::one() {
+            try!(write!(f, "{}", abs));
+        }
+
+        match k {
+            0 => {},
+            1 => try!(write!(f, "x")),
+            _ => try!(write!(f, "x^{}", k))
+        }
+
+        first = false;
+    }
+
+    if first {
+        try!(write!(f, "0"));
+    }
+
+    Ok(())
+}
+
+fn trim_zeros(mut coeffs: Vec<BigInt>) -> Vec<BigInt> {
+    while coeffs.last().map_or(false, |c| c.is_zero()) {
+        coeffs.pop();
+    }
+
+    coeffs
+}
+
+fn mat_mul(a: &Vec<Vec<BigInt>>, b: &Vec<Vec<BigInt>>) -> Vec<Vec<BigInt>> {
+    let n = a.len();
+    Vec::from_fn(n, |i| {
+        Vec::from_fn(n, |j| {
+            let mut sum: BigInt = Zero::zero();
+            for k in range(0, n) {
+                sum = sum.add(&a.get(i).get(k).mul(b.get(k).get(j)));
+            }
+
+            sum
+        })
+    })
+}
+
+/// Draws uniformly random strings from the language of a DFA.
+///
+/// Created by `DFA::sampler`. Each string is built one symbol at a time, choosing each symbol with
+/// probability proportional to the number of accepted strings that can still be completed after it.
+pub struct Sampler<'a, S = char> {
+    dfa: &'a DFA<S>,
+    alphabet: Vec<S>,
+    //suffixes.get(n).get(s) is the number of strings of length n leading from state s to an accept state
+    suffixes: Vec<Vec<BigUint>>
+}
+
+impl<'a, S: Symbol> Sampler<'a, S> {
+    /// The longest strings this sampler can produce.
+    pub fn max_length(&self) -> uint {
+        self.suffixes.len() - 1
+    }
+
+    /// Returns the number of accepted strings of exactly length symbols,
+    /// or zero if length is more than `max_length`.
+    pub fn count(&self, length: uint) -> BigUint {
+        if length > self.max_length() {
+            return Zero::zero();
+        }
+
+        self.suffixes.get(length).get(self.dfa.start).clone()
+    }
+
+    /// Returns a uniformly random accepted string of exactly length symbols,
+    /// or None if there are none or length is more than `max_length`.
+    pub fn sample<R: Rng>(&self, length: uint, rng: &mut R) -> Option<Vec<S>> {
+        if self.count(length).is_zero() {
+            return None;
+        }
+
+        let mut state = self.dfa.start;
+        let mut word = Vec::with_capacity(length);
+
+        for remaining in range(1, length + 1).rev() {
+            let mut pick = rng.gen_biguint_below(self.suffixes.get(remaining).get(state));
+
+            for sym in self.alphabet.iter() {
+                let next = match self.dfa.next_state(state, sym) {
+                    Some(n) => n,
+                    None => continue
+                };
+
+                let weight = self.suffixes.get(remaining - 1).get(next);
+                if pick < *weight {
+                    word.push(sym.clone());
+                    state = next;
+                    break;
+                }
+
+                pick = pick.sub(weight);
+            }
+        }
+
+        Some(word)
+    }
+
+    /// Returns an accepted string whose length is drawn from the given distribution,
+    /// and which is uniformly random among accepted strings of that length.
+    ///
+    /// weights.get(n) is the relative probability of length n. Lengths with no accepted strings,
+    /// or beyond `max_length`, are never chosen. Returns None if every length that could be chosen
+    /// has no accepted strings.
+    pub fn sample_lengths<R: Rng>(&self, weights: &Vec<f64>, rng: &mut R) -> Option<Vec<S>> {
+        let usable: Vec<(uint, f64)> = weights.iter().enumerate()
+            .filter(|&(len, w)| *w > 0.0 && !self.count(len).is_zero())
+            .map(|(len, w)| (len, *w))
+            .collect();
+
+        let total = usable.iter().fold(0.0, |acc, &(_, w)| acc + w);
+        if usable.is_empty() {
+            return None;
+        }
+
+        let mut pick = rng.gen::<f64>() * total;
+        for &(len, w) in usable.iter() {
+            if pick < w {
+                return self.sample(len, rng);
+            }
+
+            pick -= w;
+        }
+
+        //Rounding can leave a sliver past the last weight
+        let &(len, _) = usable.last().unwrap();
+        self.sample(len, rng)
+    }
+
+    /// Returns a uniformly random string among all accepted strings of up to `max_length` symbols,
+    /// or None if there are none.
+    pub fn sample_up_to<R: Rng>(&self, rng: &mut R) -> Option<Vec<S>> {
+        let mut total: BigUint = Zero::zero();
+        for len in range(0, self.max_length() + 1) {
+            total = total.add(&self.count(len));
+        }
+
+        if total.is_zero() {
+            return None;
+        }
+
+        let mut pick = rng.gen_biguint_below(&total);
+        for len in range(0, self.max_length() + 1) {
+            let count = self.count(len);
+            if pick < count {
+                return self.sample(len, rng);
+            }
+
+            pick = pick.sub(&count);
+        }
+
+        unreachable!()
+    }
+}
+
+/// Iterator over the strings a DFA accepts, in shortlex order.
+///
+/// Created by `DFA::accepted_words` and `DFA::accepted_words_up_to`.
+/// Each string is a Vec of symbols.
+pub struct AcceptedStrings<'a, S = char> {
+    dfa: &'a DFA<S>,
+    alphabet: Vec<S>,
+    live: BitvSet,
+    queue: RingBuf<(uint, Vec<S>, uint)>,
+    max_length: Option<uint>
+}
+
+impl<'a, S: Symbol> AcceptedStrings<'a, S> {
+    fn new(dfa: &'a DFA<S>, max_length: Option<uint>) -> AcceptedStrings<'a, S> {
+        let mut alphabet = dfa.alphabet.clone();
+        alphabet.sort();
+
+        let live = dfa.coreachable_states();
+        let mut queue = RingBuf::new();
+        if live.contains(&dfa.start) {
+            queue.push_back((dfa.start, vec!(), 0u));
+        }
+
+        AcceptedStrings {
+            dfa: dfa,
+            alphabet: alphabet,
+            live: live,
+            queue: queue,
+            max_length: max_length
+        }
+    }
+}
+
+impl<'a, S: Symbol> Iterator<Vec<S>> for AcceptedStrings<'a, S> {
+    fn next(&mut self) -> Option<Vec<S>> {
+        loop {
+            let (state, word, len) = match self.queue.pop_front() {
+                Some(entry) => entry,
+                None => return None
+            };
+
+            let can_grow = match self.max_length {
+                Some(max) => len < max,
+                None => true
+            };
+
+            //Every queued state can still reach an accept state, so the queue only
+            //runs dry once every accepted string has been produced
+            if can_grow {
+                for sym in self.alphabet.iter() {
+                    match self.dfa.next_state(state, sym) {
+                        Some(next) if self.live.contains(&next) => {
+                            let mut w = word.clone();
+                            w.push(sym.clone());
+                            self.queue.push_back((next, w, len + 1));
+                        },
+                        _ => {}
+                    }
+                }
+            }
+
+            if self.dfa.accept.contains(&state) {
+                return Some(word);
+            }
+        }
+    }
+}
+
+//Disjoint sets of states with path compression and union by rank
+struct UnionFind {
+    parent: Vec<uint>,
+    rank: Vec<uint>
+}
+
+impl UnionFind {
+    fn new(size: uint) -> UnionFind {
+        UnionFind {
+            parent: range(0, size).collect(),
+            rank: Vec::from_elem(size, 0u)
+        }
+    }
+
+    fn find(&mut self, x: uint) -> uint {
+        let p = *self.parent.get(x);
+        if p == x {
+            return x;
+        }
+
+        let root = self.find(p);
+        *self.parent.get_mut(x) = root;
+        root
+    }
+
+    //Merges the sets containing x and y. Returns false if they were already the same set.
+    fn union(&mut self, x: uint, y: uint) -> bool {
+        let rx = self.find(x);
+        let ry = self.find(y);
+        if rx == ry {
+            return false;
+        }
+
+        let (kx, ky) = (*self.rank.get(rx), *self.rank.get(ry));
+        if kx < ky {
+            *self.parent.get_mut(rx) = ry;
+        }
+
+        else if kx > ky {
+            *self.parent.get_mut(ry) = rx;
+        }
+
+        else {
+            *self.parent.get_mut(ry) = rx;
+            *self.rank.get_mut(rx) += 1;
+        }
+
+        true
+    }
+}
+
+//States split into disjoint blocks, where a block can be split by marking some of its states
+//in time proportional to the number marked
+struct Partition {
+    //The states, grouped so that the states of each block are contiguous
+    elems: Vec<uint>,
+    //Position of each state in elems
+    location: Vec<uint>,
+    //Block of each state
+    block: Vec<uint>,
+    //The states of each block are elems.slice(first.get(b), end.get(b))
+    first: Vec<uint>,
+    end: Vec<uint>,
+    //Marked states are moved to the front of their block and counted here
+    marked: Vec<uint>,
+    //Blocks with at least one marked state
+    touched: Vec<uint>
+}
+
+impl Partition {
+    //One block holding every state
+    fn new(size: uint) -> Partition {
+        Partition {
+            elems: range(0, size).collect(),
+            location: range(0, size).collect(),
+            block: Vec::from_elem(size, 0u),
+            first: vec!(0),
+            end: vec!(size),
+            marked: vec!(0),
+            touched: vec!()
+        }
+    }
+
+    fn num_blocks(&self) -> uint {
+        self.first.len()
+    }
+
+    fn block_of(&self, state: uint) -> uint {
+        *self.block.get(state)
+    }
+
+    fn size(&self, block: uint) -> uint {
+        *self.end.get(block) - *self.first.get(block)
+    }
+
+    fn states<'a>(&'a self, block: uint) -> &'a [uint] {
+        self.elems.slice(*self.first.get(block), *self.end.get(block))
+    }
+
+    fn mark(&mut self, state: uint) {
+        let b = *self.block.get(state);
+        let pos = *self.location.get(state);
+        let boundary = *self.first.get(b) + *self.marked.get(b);
+        if pos < boundary {
+            return;
+        }
+
+        //Swap state with the first unmarked state of its block
+        let other = *self.elems.get(boundary);
+        self.elems.as_mut_slice().swap(pos, boundary);
+        *self.location.get_mut(other) = pos;
+        *self.location.get_mut(state) = boundary;
+
+        if *self.marked.get(b) == 0 {
+            self.touched.push(b);
+        }
+
+        *self.marked.get_mut(b) += 1;
+    }
+
+    //Splits the marked states of each touched block into a new block and clears the marks.
+    //Returns the (old block, new block) pairs. Blocks whose states are all marked are left whole.
+    fn split(&mut self) -> Vec<(uint, uint)> {
+        let mut splits = vec!();
+        let touched = mem::replace(&mut self.touched, vec!());
+        for &b in touched.iter() {
+            let count = *self.marked.get(b);
+            *self.marked.get_mut(b) = 0;
+            if count == self.size(b) {
+                continue;
+            }
+
+            let new = self.first.len();
+            let start = *self.first.get(b);
+            self.first.push(start);
+            self.end.push(start + count);
+            self.marked.push(0);
+            *self.first.get_mut(b) = start + count;
+
+            for i in range(start, start + count) {
+                *self.block.get_mut(*self.elems.get(i)) = new;
+            }
+
+            splits.push((b, new));
+        }
+
+        splits
+    }
+}
+
+//Returns the position in remaining of the state that is cheapest to eliminate according to order
+fn cheapest_elimination(edges: &Vec<Vec<Regex>>, remaining: &Vec<uint>, order: EliminationOrder) -> uint {
+    let mut best = 0;
+    let mut best_cost = 0;
+
+    for (pos, &k) in remaining.iter().enumerate() {
+        let mut incoming = vec!();
+        let mut outgoing = vec!();
+        for i in range(0, edges.len()) {
+            if i == k {
+                continue;
+            }
+
+            if *edges.get(i).get(k) != Empty {
+                incoming.push(edges.get(i).get(k).size() as int);
+            }
+
+            if *edges.get(k).get(i) != Empty {
+                outgoing.push(edges.get(k).get(i).size() as int);
+            }
+        }
+
+        let num_in = incoming.len() as int;
+        let num_out = outgoing.len() as int;
+
+        let cost = match order {
+            LeastWeight => {
+                //Each incoming label is copied once per outgoing edge and vice versa,
+                //and the self loop once per new edge
+                let self_loop = match *edges.get(k).get(k) {
+                    Empty => 0,
+                    ref r => r.size() as int
+                };
+
+                incoming.iter().fold(0, |acc, w| acc + *w * (num_out - 1)) +
+                outgoing.iter().fold(0, |acc, w| acc + *w * (num_in - 1)) +
+                self_loop * (num_in * num_out - 1)
+            },
+            _ => num_in * num_out
         };
 
-        match eq1.union(&eq2) {
-            None => false,
-            Some(dfa) => dfa.accepts_none()
+        if pos == 0 || cost < best_cost {
+            best = pos;
+            best_cost = cost;
+        }
+    }
+
+    best
+}
+
+impl<S: Symbol> Run<S> for DFA<S> {
+    fn run<I: Word<S>>(&self, input: I) -> Option<bool> {
+        let width = self.alphabet.len();
+        //DEAD once the implicit dead state of a partial DFA is entered
+        let mut curr_state = self.start as u32;
+
+        // Compute the transition for each symbol in input, stopping at the first unknown one
+        let known = input.each_symbol(|sym| {
+            match self.column(sym) {
+                Some(col) => {
+                    //The rest of the input must still be checked for unknown symbols
+                    if curr_state != DEAD {
+                        curr_state = *self.table.get(curr_state as uint * width + col);
+                    }
+
+                    true
+                },
+                None => false
+            }
+        });
+
+        if !known {
+            return None;
         }
+
+        Some(curr_state != DEAD && self.accept.contains(&(curr_state as uint)))
+    }
+}
+
+/// **Note**: Tests for equality in terms on the language recognized, not the actual states/transitions
+/// in the DFA.
+impl<S: Symbol> PartialEq for DFA<S> {
+    /// Uses the Hopcroft-Karp algorithm, see `DFA::equivalent`.
+    fn eq(&self, other: &DFA<S>) -> bool {
+        self.equivalent(other)
     }
 }
 
-impl Eq for DFA {}
+impl<S: Symbol> Eq for DFA<S> {}
+
+/// `a | b` is `a.union_aligned(&b)`
+impl<S: Symbol> BitOr<DFA<S>, DFA<S>> for DFA<S> {
+    fn bitor(&self, rhs: &DFA<S>) -> DFA<S> {
+        self.union_aligned(rhs)
+    }
+}
+
+/// `a & b` is `a.intersect_aligned(&b)`
+impl<S: Symbol> BitAnd<DFA<S>, DFA<S>> for DFA<S> {
+    fn bitand(&self, rhs: &DFA<S>) -> DFA<S> {
+        self.intersect_aligned(rhs)
+    }
+}
+
+/// `a - b` is `a.difference(&b)`
+impl<S: Symbol> Sub<DFA<S>, DFA<S>> for DFA<S> {
+    fn sub(&self, rhs: &DFA<S>) -> DFA<S> {
+        self.difference(rhs)
+    }
+}
 
-impl fmt::Show for DFA {
+/// `a ^ b` is `a.symmetric_difference(&b)`
+impl<S: Symbol> BitXor<DFA<S>, DFA<S>> for DFA<S> {
+    fn bitxor(&self, rhs: &DFA<S>) -> DFA<S> {
+        self.symmetric_difference(rhs)
+    }
+}
+
+/// `!a` is `a.complement()`
+impl<S: Symbol> Not<DFA<S>> for DFA<S> {
+    fn not(&self) -> DFA<S> {
+        self.complement()
+    }
+}
+
+impl<S: Symbol + fmt::Show> fmt::Show for DFA<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(f, "Alphabet: {}\n", self.alphabet));
-        try!(write!(f, "Start State: {}\n", self.start));
-        try!(write!(f, "Accept States: {}\n", self.accept));
-        try!(write!(f, "Transitions:\n"));
-
-        let mut temp = vec!();
-        for (&(curr, sym), next) in self.delta.iter() {
-            temp.push((curr, sym, next));
+        try!(write!(f, "Start State: {}\n", self.state_label(self.start)));
+        match self.labels {
+            None => try!(write!(f, "Accept States: {}\n", self.accept)),
+            Some(_) => {
+                let names: Vec<String> = self.accept.iter().map(|s| self.state_label(s)).collect();
+                try!(write!(f, "Accept States: {}\n", names));
+            }
         }
+        try!(write!(f, "Transitions:\n"));
 
-        temp.sort();
-        for &(curr, sym, next) in temp.iter() {
-            try!(write!(f, "  ({}, '{}') -> {}\n", curr, sym, next));
+        //Columns are sorted by symbol, so transitions are listed in order
+        for curr in range(0, self.num_states) {
+            for &(ref sym, col) in self.columns.iter() {
+                match self.next(curr, col) {
+                    Some(next) => {
+                        try!(write!(f, "  ({}, '{}') -> {}\n", self.state_label(curr), sym, self.state_label(next)));
+                    },
+                    None => {}
+                }
+            }
         }
         Ok(())
     }
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::any::{Any, AnyRefExt};
+use super::Transition;
+
+/// A problem with the description of an automaton passed to a constructor.
+///
+/// Transitions and symbols in the error have the automaton's symbol type.
+#[deriving(Clone, PartialEq)]
+pub enum AutomatonError<S = char> {
+    /// The automaton has no states.
+    NoStates,
+    /// A DFA has the given number of states, too many to number with 32 bits.
+    TooManyStates(uint),
+    /// A DFA was given the first number of transitions instead of one per state and symbol,
+    /// which is the second.
+    TransitionCount(uint, uint),
+    /// A transition reads a symbol that is not in the alphabet.
+    UnknownSymbol(Transition<S>),
+    /// A transition refers to the given state, which does not exist.
+    StateOutOfRange(Transition<S>, uint),
+    /// An epsilon transition, given as (current state, next state), refers to the given state,
+    /// which does not exist.
+    EpsilonOutOfRange((uint, uint), uint),
+    /// A DFA state has more than one transition on the same symbol.
+    DuplicateTransition(Transition<S>),
+    /// A DFA state has no transition on the symbol.
+    MissingTransition(uint, S),
+    /// The alphabet contains a symbol reserved for internal use.
+    ReservedSymbol(S),
+    /// A range of symbols starts after it ends.
+    InvalidRange(S, S),
+    /// The start state does not exist.
+    InvalidStart(uint),
+    /// An accept state does not exist.
+    InvalidAccept(uint),
+    /// A builder was given a transition on a state name that was never added.
+    UnknownState(String),
+    /// A builder has no start state.
+    NoStart,
+    /// A builder has the two named start states, and maybe more.
+    MultipleStarts(String, String)
+}
+
+//Shows a symbol in quotes if it is a char, and as it is otherwise
+struct Quoted<'a, S>(&'a S);
+
+impl<'a, S: fmt::Show + 'static> fmt::Show for Quoted<'a, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let &Quoted(sym) = self;
+        match (sym as &Any).as_ref::<char>() {
+            Some(c) => write!(f, "'{}'", c),
+            None => write!(f, "{}", sym)
+        }
+    }
+}
+
+impl<S: fmt::Show + 'static> fmt::Show for AutomatonError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NoStates => write!(f, "Must contain at least one state"),
+            TooManyStates(count) => write!(f, "Too many states: {}", count),
+            TransitionCount(..) => write!(f, "Incorrect number of transitions"),
+            UnknownSymbol((_, ref sym, _)) => write!(f, "Symbol `{}` is not in the alphabet", sym),
+            StateOutOfRange((curr, ref sym, next), state) => {
+                write!(f, "In transition: ({}, {}) -> {}: State `{}` does not exist",
+                       curr, Quoted(sym), next, state)
+            },
+            EpsilonOutOfRange((curr, next), state) => {
+                write!(f, "In epsilon transition: {} -> {}: State `{}` does not exist",
+                       curr, next, state)
+            },
+            DuplicateTransition((curr, ref sym, next)) => {
+                write!(f, "Duplicate transition: ({}, {}) -> {}", curr, Quoted(sym), next)
+            },
+            MissingTransition(state, ref sym) => write!(f, "Missing transition: ({}, {})", state, Quoted(sym)),
+            ReservedSymbol(ref sym) => write!(f, "Alphabets cannot contain {}", Quoted(sym)),
+            InvalidRange(ref lo, ref hi) => write!(f, "Invalid range: {} comes after {}", Quoted(lo), Quoted(hi)),
+            InvalidStart(_) => write!(f, "Invalid start state"),
+            InvalidAccept(state) => write!(f, "Invalid accept state `{}`", state),
+            UnknownState(ref label) => write!(f, "State `{}` does not exist", label),
+            NoStart => write!(f, "No start state"),
+            MultipleStarts(ref first, ref second) => {
+                write!(f, "Both `{}` and `{}` are start states", first, second)
+            }
+        }
+    }
+}
+
+/// A suspicious but legal part of an automaton.
+#[deriving(Clone, PartialEq)]
+pub enum AutomatonWarning {
+    /// The state cannot be reached from the start state.
+    UnreachableState(uint),
+    /// The state is reachable but cannot reach any accept state.
+    DeadState(uint)
+}
+
+impl fmt::Show for AutomatonWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UnreachableState(state) => write!(f, "State `{}` is unreachable from the start state", state),
+            DeadState(state) => write!(f, "State `{}` cannot reach an accept state", state)
+        }
+    }
+}
+
+/// Every problem found in the description of an automaton.
+#[deriving(Clone, PartialEq)]
+pub struct ValidationReport<S = char> {
+    /// Problems that prevent the automaton from being constructed.
+    pub errors: Vec<AutomatonError<S>>,
+    /// Problems that do not prevent construction.
+    pub warnings: Vec<AutomatonWarning>
+}
+
+impl<S> ValidationReport<S> {
+    /// Returns true if there are no errors. There may still be warnings.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl<S: fmt::Show + 'static> fmt::Show for ValidationReport<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for e in self.errors.iter() {
+            try!(write!(f, "Error: {}\n", e));
+        }
+
+        for w in self.warnings.iter() {
+            try!(write!(f, "Warning: {}\n", w));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::bitv::BitvSet;
+
+//Helpers for treating an automaton as a directed graph whose edges are weighted by
+//the number of symbols they read, so epsilon transitions have weight 0.
+
+//Length of the longest path from a start node to a target node
+pub enum PathLength {
+    NoPath,
+    Finite(uint),
+    Unbounded
+}
+
+//Finds the longest path from start to any of targets through the graph with the given
+//(from, to, weight) edges. Only nodes on some path from start to a target are considered.
+//The longest path is unbounded if those nodes contain a cycle with positive weight.
+pub fn longest_path(num_nodes: uint, edges: &Vec<(uint, uint, uint)>,
+                    start: uint, targets: &BitvSet) -> PathLength {
+    let mut forward = Vec::from_elem(num_nodes, vec!());
+    let mut backward = Vec::from_elem(num_nodes, vec!());
+    for &(from, to, _) in edges.iter() {
+        forward.get_mut(from).push(to);
+        backward.get_mut(to).push(from);
+    }
+
+    let mut start_set = BitvSet::new();
+    start_set.insert(start);
+    let mut useful = reachable(&forward, &start_set);
+    useful.intersect_with(&reachable(&backward, targets));
+
+    if !useful.contains(&start) {
+        return NoPath;
+    }
+
+    let (component, num_components) = components(&forward, &backward, &useful);
+
+    //Components are numbered in topological order, so every useful edge goes from a lower
+    //or equal numbered component to a higher or equal one
+    let mut useful_edges: Vec<(uint, uint, uint)> = vec!();
+    for &(from, to, weight) in edges.iter() {
+        if !useful.contains(&from) || !useful.contains(&to) {
+            continue;
+        }
+
+        let (cf, ct) = (*component.get(from) as uint, *component.get(to) as uint);
+        if cf == ct {
+            if weight > 0 {
+                return Unbounded;
+            }
+
+            continue;
+        }
+
+        useful_edges.push((cf, ct, weight));
+    }
+
+    useful_edges.sort();
+
+    let mut best: Vec<Option<uint>> = Vec::from_elem(num_components, None);
+    *best.get_mut(*component.get(start) as uint) = Some(0);
+
+    for &(cf, ct, weight) in useful_edges.iter() {
+        match *best.get(cf) {
+            None => {},
+            Some(len) => {
+                let longer = match *best.get(ct) {
+                    Some(old) => old < len + weight,
+                    None => true
+                };
+
+                if longer {
+                    *best.get_mut(ct) = Some(len + weight);
+                }
+            }
+        }
+    }
+
+    let mut longest = 0;
+    for t in targets.iter() {
+        if useful.contains(&t) {
+            match *best.get(*component.get(t) as uint) {
+                Some(len) if len > longest => longest = len,
+                _ => {}
+            }
+        }
+    }
+
+    Finite(longest)
+}
+
+//All nodes reachable from sources by following adjacency lists
+pub fn reachable(adjacent: &Vec<Vec<uint>>, sources: &BitvSet) -> BitvSet {
+    let mut seen = sources.clone();
+    let mut stack: Vec<uint> = sources.iter().filter(|&s| s < adjacent.len()).collect();
+
+    loop {
+        let node = match stack.pop() {
+            Some(n) => n,
+            None => break
+        };
+
+        for &next in adjacent.get(node).iter() {
+            if seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+
+    seen
+}
+
+//Strongly connected components of the nodes in within, by Kosaraju's algorithm.
+//Components are numbered in topological order, and nodes outside of within get -1.
+//Also returns the number of components.
+fn components(forward: &Vec<Vec<uint>>, backward: &Vec<Vec<uint>>,
+              within: &BitvSet) -> (Vec<int>, uint) {
+    //First pass: order nodes by when their depth first search finishes
+    let mut order = vec!();
+    let mut visited = BitvSet::new();
+    for root in within.iter() {
+        if !visited.insert(root) {
+            continue;
+        }
+
+        //Each entry is a node and the position of the next neighbour to visit
+        let mut stack = vec!((root, 0u));
+        loop {
+            let (node, pos) = match stack.pop() {
+                Some(entry) => entry,
+                None => break
+            };
+
+            let neighbours = forward.get(node);
+            if pos < neighbours.len() {
+                stack.push((node, pos + 1));
+                let next = *neighbours.get(pos);
+                if within.contains(&next) && visited.insert(next) {
+                    stack.push((next, 0));
+                }
+            }
+
+            else {
+                order.push(node);
+            }
+        }
+    }
+
+    //Second pass: search the reversed graph in reverse finishing order
+    let mut component = Vec::from_elem(forward.len(), -1i);
+    let mut count = 0i;
+    for &root in order.iter().rev() {
+        if *component.get(root) != -1 {
+            continue;
+        }
+
+        *component.get_mut(root) = count;
+        let mut stack = vec!(root);
+        loop {
+            let node = match stack.pop() {
+                Some(n) => n,
+                None => break
+            };
+
+            for &prev in backward.get(node).iter() {
+                if within.contains(&prev) && *component.get(prev) == -1 {
+                    *component.get_mut(prev) = count;
+                    stack.push(prev);
+                }
+            }
+        }
+
+        count += 1;
+    }
+
+    (component, count as uint)
+}
@@ -1,25 +1,116 @@
 #![crate_id = "hephaestus"]
 #![deny(missing_doc)]
+#![feature(macro_rules, default_type_params)]
 
 //! Implementations of various types of automata in Rust. <br>
 //! I was inspired to write this after taking CS 181 at UCLA.
 extern crate collections;
+extern crate num;
+
+use std::hash::Hash;
 
 pub use DFA = dfa::DFA;
 pub use NFA = nfa::NFA;
+pub use RangeDFA = ranges::RangeDFA;
+pub use SFA = sfa::SFA;
+pub use CharSet = sfa::CharSet;
+pub use Predicate = sfa::Predicate;
+pub use AutomatonError = error::AutomatonError;
+pub use DfaBuilder = builder::DfaBuilder;
+pub use NfaBuilder = builder::NfaBuilder;
 
 /// A 3-tuple representing a state transition.
 ///
 /// It has the form: **(current state, symbol, next state)**
-pub type Transition = (uint, char, uint);
+pub type Transition<S = char> = (uint, S, uint);
+
+/// A transition on every char of an inclusive range.
+///
+/// It has the form: **(current state, (first char, last char), next state)**
+pub type RangeTransition = (uint, (char, char), uint);
+
+/// A symbol of an automaton's alphabet.
+///
+/// Every type that can be compared, ordered, hashed and cloned is a symbol,
+/// so alphabets can hold chars, bytes, tokens or the variants of an enum.
+pub trait Symbol: Eq + Ord + Hash + Clone {}
+
+impl<S: Eq + Ord + Hash + Clone> Symbol for S {}
+
+/// A sequence of symbols that an automaton can read.
+///
+/// A `&str` is read as its chars, and a slice as its elements.
+pub trait Word<S> {
+    /// Returns the symbols in order.
+    fn symbols(&self) -> Vec<S>;
+
+    /// Calls f on each symbol in order without collecting them, stopping early if f returns false.
+    ///
+    /// Returns false if f stopped the iteration.
+    fn each_symbol(&self, f: |&S| -> bool) -> bool {
+        self.symbols().iter().all(f)
+    }
+}
+
+impl<'a> Word<char> for &'a str {
+    fn symbols(&self) -> Vec<char> {
+        self.chars().collect()
+    }
+
+    fn each_symbol(&self, f: |&char| -> bool) -> bool {
+        self.chars().all(|c| f(&c))
+    }
+}
+
+impl<'a, S: Clone> Word<S> for &'a [S] {
+    fn symbols(&self) -> Vec<S> {
+        self.to_vec()
+    }
+
+    fn each_symbol(&self, f: |&S| -> bool) -> bool {
+        self.iter().all(f)
+    }
+}
+
+/// An iterator that turns the words of a char automaton into Strings.
+///
+/// Returned by the `String` producing methods of `DFA<char>` and `NFA<char>`,
+/// which wrap the iterators of their generic counterparts.
+pub struct Strings<I> {
+    words: I
+}
+
+impl<I: Iterator<Vec<char>>> Strings<I> {
+    fn new(words: I) -> Strings<I> {
+        Strings { words: words }
+    }
+}
+
+impl<I: Iterator<Vec<char>>> Iterator<String> for Strings<I> {
+    fn next(&mut self) -> Option<String> {
+        self.words.next().map(|word| word.move_iter().collect())
+    }
+}
 
 /// Basic trait abstracting over all automata.  
-/// Checks if an automaton accepts a given string.
-pub trait Run {
-    /// Returns a boolean representing if the automaton accepts the string, or None
-    /// if the string contains characters not in the automaton's alphabet.
-    fn run(&self, string: &str) -> Option<bool>;
+/// Checks if an automaton accepts a given string of symbols.
+pub trait Run<S = char> {
+    /// Returns a boolean representing if the automaton accepts the input, or None
+    /// if the input contains symbols not in the automaton's alphabet.
+    fn run<I: Word<S>>(&self, input: I) -> Option<bool>;
 }
 
+mod macros;
 mod dfa;
 mod nfa;
+mod ranges;
+mod sfa;
+mod graph;
+mod validate;
+mod builder;
+
+/// Errors reported when constructing automata.
+pub mod error;
+
+/// Regular expressions and their compilation to NFAs.
+pub mod regex;
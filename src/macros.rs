@@ -0,0 +1,124 @@
+//Macros for writing automata as lists of named states and transitions.
+//They expand to uses of DfaBuilder and NfaBuilder, so the grammar and the symbols are checked
+//when the caller is compiled and everything else is checked when the automaton is built.
+
+/// Builds a DFA from named states and transitions, returning a
+/// `Result<DFA, AutomatonError>` like `DfaBuilder::build`.
+///
+/// The start state comes first, then the accept states, then the transitions.
+/// A transition may read several symbols, separated by commas. Symbols are single
+/// letters, digits or char literals, and anything else fails to compile.
+/// The alphabet is every symbol used.
+///
+/// ```ignore
+/// let dfa = dfa!{
+///     start q0;
+///     accept q1;
+///     q0 -a-> q1;
+///     q0 -b-> q0;
+///     q1 -a,b-> q1;
+/// };
+/// ```
+#[macro_export]
+macro_rules! dfa(
+    (start $start:ident; accept $($accept:ident),*;
+     $($from:ident - $($sym:tt),+ -> $to:ident;)*) => ({
+        let mut alphabet: Vec<char> = vec!($($(__hephaestus_symbol!($sym)),+),*);
+        alphabet.sort();
+        alphabet.dedup();
+
+        let mut builder = ::hephaestus::DfaBuilder::new(&alphabet);
+        builder.start_state(stringify!($start));
+        $(builder.accept_state(stringify!($accept));)*
+        $(builder.state(stringify!($from)).state(stringify!($to));)*
+        $(builder.transitions(stringify!($from),
+                              &[$(__hephaestus_symbol!($sym)),+],
+                              stringify!($to));)*
+        builder.build()
+    })
+)
+
+/// Builds an NFA from named states and transitions, returning a
+/// `Result<NFA, AutomatonError>` like `NfaBuilder::build`.
+///
+/// Uses the same syntax as `dfa!`, and an arrow with no symbols, such as
+/// `q0 --> q1;`, is an epsilon transition.
+///
+/// ```ignore
+/// let nfa = nfa!{
+///     start q0;
+///     accept q2;
+///     q0 --> q1;
+///     q1 -a,b-> q2;
+/// };
+/// ```
+#[macro_export]
+macro_rules! nfa(
+    (start $start:ident; accept $($accept:ident),*; $($rest:tt)*) => ({
+        let mut edges: Vec<(&'static str, Vec<char>, &'static str)> = vec!();
+        nfa!(edges edges; $($rest)*);
+
+        let mut alphabet = vec!();
+        for &(_, ref syms, _) in edges.iter() {
+            alphabet.push_all(syms.as_slice());
+        }
+
+        alphabet.sort();
+        alphabet.dedup();
+
+        let mut builder = ::hephaestus::NfaBuilder::new(&alphabet);
+        builder.start_state(stringify!($start));
+        $(builder.accept_state(stringify!($accept));)*
+        for &(from, ref syms, to) in edges.iter() {
+            builder.state(from).state(to);
+            if syms.is_empty() {
+                builder.epsilon(from, to);
+            }
+
+            else {
+                builder.transitions(from, syms.as_slice(), to);
+            }
+        }
+
+        builder.build()
+    });
+
+    //Collects each transition into edges, with no symbols for an epsilon transition
+    (edges $edges:ident; ) => (());
+    (edges $edges:ident; $from:ident - -> $to:ident; $($rest:tt)*) => ({
+        $edges.push((stringify!($from), vec!(), stringify!($to)));
+        nfa!(edges $edges; $($rest)*)
+    });
+    (edges $edges:ident; $from:ident - $($sym:tt),+ -> $to:ident; $($rest:tt)*) => ({
+        $edges.push((stringify!($from),
+                     vec!($(__hephaestus_symbol!($sym)),+),
+                     stringify!($to)));
+        nfa!(edges $edges; $($rest)*)
+    })
+)
+
+//Turns a symbol written in `dfa!` or `nfa!` into a char. Single letter identifiers and digits
+//become their char, and any other identifier expands to a name that does not exist, so it fails
+//to compile. The remaining tokens must be char literals, as they initialize a static char,
+//which cannot refer to local variables.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __hephaestus_symbol(
+    (a) => ('a'); (b) => ('b'); (c) => ('c'); (d) => ('d'); (e) => ('e'); (f) => ('f');
+    (g) => ('g'); (h) => ('h'); (i) => ('i'); (j) => ('j'); (k) => ('k'); (l) => ('l');
+    (m) => ('m'); (n) => ('n'); (o) => ('o'); (p) => ('p'); (q) => ('q'); (r) => ('r');
+    (s) => ('s'); (t) => ('t'); (u) => ('u'); (v) => ('v'); (w) => ('w'); (x) => ('x');
+    (y) => ('y'); (z) => ('z');
+    (A) => ('A'); (B) => ('B'); (C) => ('C'); (D) => ('D'); (E) => ('E'); (F) => ('F');
+    (G) => ('G'); (H) => ('H'); (I) => ('I'); (J) => ('J'); (K) => ('K'); (L) => ('L');
+    (M) => ('M'); (N) => ('N'); (O) => ('O'); (P) => ('P'); (Q) => ('Q'); (R) => ('R');
+    (S) => ('S'); (T) => ('T'); (U) => ('U'); (V) => ('V'); (W) => ('W'); (X) => ('X');
+    (Y) => ('Y'); (Z) => ('Z');
+    (0) => ('0'); (1) => ('1'); (2) => ('2'); (3) => ('3'); (4) => ('4');
+    (5) => ('5'); (6) => ('6'); (7) => ('7'); (8) => ('8'); (9) => ('9');
+    ($name:ident) => (symbols_must_be_single_letters_digits_or_char_literals);
+    ($literal:tt) => ({
+        static SYMBOL: char = $literal;
+        SYMBOL
+    })
+)
@@ -1,69 +1,122 @@
 use std::collections::bitv::BitvSet;
 use std::collections::hashmap::HashMap;
+use std::hash::Hash;
+use std::collections::ringbuf::RingBuf;
+use std::collections::Deque;
 use std::fmt;
-use super::{Run, Transition};
+use super::{Run, Word, Symbol, Transition, DFA, Strings};
+use super::error::{AutomatonError, UnknownSymbol, StateOutOfRange, ReservedSymbol};
+use super::error::{InvalidStart, InvalidAccept, ValidationReport};
+use super::validate;
+use super::validate::Written;
+use super::graph;
+use super::graph::{NoPath, Finite, Unbounded};
 
 /// Nondeterministic Finite Automaton.
 ///
 /// Similar in principle to a DFA except that an NFA can have 0 or
 /// more transitions on an input symbol, and can transition
-/// on the empty string (signified by '_'). They are equivalent to DFAs in what
+/// on the empty string. They are equivalent to DFAs in what
 /// their computational power.
 ///
 /// An NFA accepts a string if **any** path makes it end up in an accept state.
-pub struct NFA {
+///
+/// Like a DFA, an NFA can use any `Symbol`, and uses chars by default.
+pub struct NFA<S = char> {
     start: uint,
-    alphabet: Vec<char>,
-    delta: HashMap<(uint, char), BitvSet>,
+    alphabet: Vec<S>,
+    delta: HashMap<(uint, S), BitvSet>,
+    //States reachable from each state by a single epsilon transition
+    epsilon: HashMap<uint, BitvSet>,
     accept:BitvSet,
-    num_states: uint
+    num_states: uint,
+    //Names of the states, if they were given any
+    labels: Option<Vec<String>>
 }
 
-impl NFA {
-    /// Builds an NFA.
+impl<S: Symbol> NFA<S> {
+    /// Builds an NFA with epsilon transitions given separately as (current state, next state) pairs.
     ///
-    /// Returns an Err if '_' is included in the alphabet or
-    /// if a transition contains a state or symbol that does not exist.
-    pub fn new(
+    /// Every transition in transitions reads a symbol, so any symbol can be in the alphabet.
+    ///
+    /// Returns an Err if the start or an accept state does not exist,
+    /// or if a transition contains a state or symbol that does not exist.
+    pub fn with_epsilons(
         num_states: uint,
-        alphabet: &Vec<char>,
-        transitions: &Vec<Transition>,
+        alphabet: &Vec<S>,
+        transitions: &Vec<Transition<S>>,
+        epsilons: &Vec<(uint, uint)>,
         start: uint,
         accept: &Vec<uint>
-    ) -> Result<NFA, String> {
+    ) -> Result<NFA<S>, AutomatonError<S>> {
 
-        let mut trns_fn: HashMap<(uint, char), BitvSet> = HashMap::with_capacity(transitions.len());
+        NFA::build(num_states, alphabet, written(transitions, epsilons).move_iter(), start, accept,
+                   |t, state| validate::out_of_range(t, state))
+    }
 
-        if alphabet.contains(&'_') {
-            return Err(format!("Alphabets cannot contain '_'"));
+    /// Checks a description of an NFA with separate epsilon transitions for every problem at once.
+    ///
+    /// Takes the same arguments as `NFA::with_epsilons`, which succeeds exactly when the report has
+    /// no errors. Unreachable and dead states are reported as warnings.
+    pub fn validate_with_epsilons(
+        num_states: uint,
+        alphabet: &Vec<S>,
+        transitions: &Vec<Transition<S>>,
+        epsilons: &Vec<(uint, uint)>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> ValidationReport<S> {
+
+        let report = ValidationReport { errors: vec!(), warnings: vec!() };
+        NFA::check(report, num_states, alphabet, written(transitions, epsilons).move_iter(), start, accept,
+                   |t, state| validate::out_of_range(t, state))
+    }
+
+    //Builds an NFA from transitions as they were written, checking the start and accept states
+    //and then each transition in order. out_of_range gives the error for a transition on the
+    //given state, which does not exist.
+    fn build<I: Iterator<Written<S>>>(
+        num_states: uint,
+        alphabet: &Vec<S>,
+        mut transitions: I,
+        start: uint,
+        accept: &Vec<uint>,
+        out_of_range: |&Written<S>, uint| -> AutomatonError<S>
+    ) -> Result<NFA<S>, AutomatonError<S>> {
+
+        let mut trns_fn: HashMap<(uint, S), BitvSet> = HashMap::new();
+        let mut epsilon: HashMap<uint, BitvSet> = HashMap::new();
+
+        if start >= num_states {
+            return Err(InvalidStart(start));
         }
 
-        // Validate transitions and add them to the transition table
-        for &(curr, sym, next) in transitions.iter() {
-            if sym != '_' && !alphabet.contains(&sym) {
-                return Err(format!("Symbol `{}` is not in the alphabet", sym));
+        for &s in accept.iter() {
+            if s >= num_states {
+                return Err(InvalidAccept(s));
             }
+        }
 
-            if curr >= num_states {
-                return Err(format!("In transition: ({}, '{}') -> {}: State `{}` \
-                                    does not exist", curr, sym, next, curr));
+        // Validate transitions and add them to the transition table
+        for t in transitions {
+            match t {
+                (curr, Some(ref sym), next) if !alphabet.contains(sym) => {
+                    return Err(UnknownSymbol((curr, sym.clone(), next)));
+                },
+                _ => {}
             }
 
-            if next >= num_states {
-                return Err(format!("In transition: ({}, '{}') -> {}: State `{}` \
-                                    does not exist", curr, sym, next, next));
+            let (curr, next) = match t { (curr, _, next) => (curr, next) };
+            for &state in [curr, next].iter() {
+                if state >= num_states {
+                    return Err(out_of_range(&t, state));
+                }
             }
 
-            trns_fn.find_with_or_insert_with((curr, sym), next,
-                //If the BitvSet exists, add next to it
-                |_, old, new| { old.insert(new); }, 
-
-                //If no match found, create a new BitvSet and add it
-                |_, v| {
-                    let mut bv = BitvSet::new();
-                    bv.insert(v);
-                    bv }
-            );
+            match t {
+                (curr, Some(sym), next) => add_transition(&mut trns_fn, (curr, sym), next),
+                (curr, None, next) => add_transition(&mut epsilon, curr, next)
+            }
         }
 
         let mut accept_bv = BitvSet::new();
@@ -76,19 +129,752 @@ impl NFA {
             start: start,
             alphabet: alphabet.clone(),
             delta: trns_fn,
-            num_states: num_states
+            epsilon: epsilon,
+            num_states: num_states,
+            labels: None
         })
     }
+
+    //Adds every problem with transitions as they were written, in order, and with the start and
+    //accept states to report. out_of_range is as in `build`.
+    fn check<I: Iterator<Written<S>>>(
+        mut report: ValidationReport<S>,
+        num_states: uint,
+        alphabet: &Vec<S>,
+        transitions: I,
+        start: uint,
+        accept: &Vec<uint>,
+        out_of_range: |&Written<S>, uint| -> AutomatonError<S>
+    ) -> ValidationReport<S> {
+
+        let valid = validate::check_transitions(&mut report, num_states, alphabet, transitions, out_of_range);
+        let edges = valid.iter().map(|&(curr, _, next)| (curr, next)).collect();
+        validate::check_states(&mut report, num_states, start, accept, &edges);
+        report
+    }
+
+    /// Returns self with its states named by labels, in order.
+    ///
+    /// Names are used when printing self and in traces. States without a label
+    /// are shown by number.
+    pub fn with_labels(mut self, labels: Vec<String>) -> NFA<S> {
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Returns the name of state, or its number if it has no name.
+    pub fn state_label(&self, state: uint) -> String {
+        match self.labels {
+            Some(ref labels) if state < labels.len() => labels.get(state).clone(),
+            _ => state.to_string()
+        }
+    }
+
+    /// Returns the number of the state with the given name.
+    pub fn find_state(&self, label: &str) -> Option<uint> {
+        match self.labels {
+            Some(ref labels) => labels.iter().position(|l| l.as_slice() == label),
+            None => None
+        }
+    }
+
+    /// Returns the names of the sets of states self can be in while reading input,
+    /// starting with the epsilon closure of the start state.
+    ///
+    /// Returns None if input contains a symbol not in the alphabet.
+    pub fn trace<I: Word<S>>(&self, input: I) -> Option<Vec<Vec<String>>> {
+        let symbols = input.symbols();
+        if symbols.iter().any(|sym| !self.alphabet.contains(sym)) {
+            return None;
+        }
+
+        let mut curr_states = BitvSet::new();
+        curr_states.insert(self.start);
+        epsilons(&mut curr_states, &self.epsilon);
+
+        let mut trace = vec!(self.state_labels(&curr_states));
+        for sym in symbols.iter() {
+            curr_states = self.step(&curr_states, sym);
+            trace.push(self.state_labels(&curr_states));
+        }
+
+        Some(trace)
+    }
+
+    //Names of each state in states
+    fn state_labels(&self, states: &BitvSet) -> Vec<String> {
+        states.iter().map(|s| self.state_label(s)).collect()
+    }
+
+    /// Compiles the NFA into an equivalent DFA using the subset construction.
+    ///
+    /// Each state of the DFA is the epsilon closure of a set of NFA states.
+    /// Only the subsets reachable from the start state are built. If any input can
+    /// leave the NFA with no live states, the empty subset becomes an explicit sink state,
+    /// so the resulting DFA is always complete.
+    pub fn to_dfa(&self) -> DFA<S> {
+        self.to_dfa_from(&vec!(self.start))
+    }
+
+    /// Compiles the NFA into a DFA using the subset construction, starting from
+    /// every state in starts at once instead of from the start state.
+    ///
+    /// The DFA's start state is the epsilon closure of starts, and it accepts a string if
+    /// any path from any state in starts does. Every state in starts must be a state of self.
+    pub fn to_dfa_from(&self, starts: &Vec<uint>) -> DFA<S> {
+        let mut start = BitvSet::new();
+        for &s in starts.iter() {
+            start.insert(s);
+        }
+
+        epsilons(&mut start, &self.epsilon);
+
+        //Each subset is keyed by its sorted list of states and numbered in the order found
+        let mut state_map: HashMap<Vec<uint>, uint> = HashMap::new();
+        state_map.insert(start.iter().collect(), 0);
+
+        let mut subsets = vec!(start);
+        let mut transitions = vec!();
+        let mut accept = vec!();
+        let mut curr = 0;
+
+        //subsets grows as new sets are discovered, so loop until every one has been expanded
+        while curr < subsets.len() {
+            let set = subsets.get(curr).clone();
+
+            if self.accept.iter().any(|x| set.contains(&x)) {
+                accept.push(curr);
+            }
+
+            for sym in self.alphabet.iter() {
+                let next = self.step(&set, sym);
+                let key: Vec<uint> = next.iter().collect();
+                let idx = match state_map.find_copy(&key) {
+                    Some(idx) => idx,
+                    None => {
+                        let idx = subsets.len();
+                        state_map.insert(key, idx);
+                        subsets.push(next);
+                        idx
+                    }
+                };
+
+                transitions.push((curr, sym.clone(), idx));
+            }
+
+            curr += 1;
+        }
+
+        //The transitions are complete and in range by construction
+        DFA::new(subsets.len(), &self.alphabet, &transitions, 0, &accept).ok().unwrap()
+    }
+
+    /// Returns an NFA accepting the concatenation of self and other.
+    ///
+    /// It accepts any string made of a string self accepts followed by one other accepts.
+    /// The alphabet of the result is the union of both alphabets.
+    pub fn concat(&self, other: &NFA<S>) -> NFA<S> {
+        let offset = self.num_states;
+        let mut delta = self.delta.clone();
+        let mut epsilon = self.epsilon.clone();
+        other.copy_transitions(offset, &mut delta, &mut epsilon);
+
+        //Link every accept state of self to the start of other
+        for i in self.accept.iter() {
+            add_transition(&mut epsilon, i, other.start + offset);
+        }
+
+        NFA {
+            accept: shift_states(&other.accept, offset),
+            start: self.start,
+            alphabet: merge_alphabets(&self.alphabet, &other.alphabet),
+            delta: delta,
+            epsilon: epsilon,
+            num_states: self.num_states + other.num_states,
+            labels: None
+        }
+    }
+
+    /// Returns an NFA accepting the union of self and other.
+    ///
+    /// A new start state is added with epsilon transitions to the start states of both inputs.
+    /// The alphabet of the result is the union of both alphabets.
+    pub fn union(&self, other: &NFA<S>) -> NFA<S> {
+        let other_offset = self.num_states + 1;
+        let mut delta = HashMap::new();
+        let mut epsilon = HashMap::new();
+        self.copy_transitions(1, &mut delta, &mut epsilon);
+        other.copy_transitions(other_offset, &mut delta, &mut epsilon);
+
+        add_transition(&mut epsilon, 0, self.start + 1);
+        add_transition(&mut epsilon, 0, other.start + other_offset);
+
+        let mut accept = shift_states(&self.accept, 1);
+        accept.union_with(&shift_states(&other.accept, other_offset));
+
+        NFA {
+            accept: accept,
+            start: 0,
+            alphabet: merge_alphabets(&self.alphabet, &other.alphabet),
+            delta: delta,
+            epsilon: epsilon,
+            num_states: self.num_states + other.num_states + 1,
+            labels: None
+        }
+    }
+
+    /// Returns an NFA accepting the Kleene star of self.
+    ///
+    /// It accepts any concatenation of zero or more strings that self accepts.
+    pub fn star(&self) -> NFA<S> {
+        //The new start state accepts the empty string, and every accept state
+        //loops back to it so another repetition can begin
+        let mut delta = HashMap::new();
+        let mut epsilon = HashMap::new();
+        self.copy_transitions(1, &mut delta, &mut epsilon);
+
+        add_transition(&mut epsilon, 0, self.start + 1);
+        for i in self.accept.iter() {
+            add_transition(&mut epsilon, i + 1, 0);
+        }
+
+        let mut accept = BitvSet::new();
+        accept.insert(0);
+
+        NFA {
+            accept: accept,
+            start: 0,
+            alphabet: self.alphabet.clone(),
+            delta: delta,
+            epsilon: epsilon,
+            num_states: self.num_states + 1,
+            labels: None
+        }
+    }
+
+    /// Returns an NFA accepting one or more repetitions of strings self accepts.
+    pub fn plus(&self) -> NFA<S> {
+        //Looping the accept states back to the start is enough here. Unlike star,
+        //the start state does not need to accept, so no new state is required.
+        let mut nfa = self.clone();
+        for i in self.accept.iter() {
+            add_transition(&mut nfa.epsilon, i, self.start);
+        }
+
+        nfa
+    }
+
+    /// Returns an NFA accepting the empty string and every string self accepts.
+    pub fn optional(&self) -> NFA<S> {
+        let mut delta = HashMap::new();
+        let mut epsilon = HashMap::new();
+        self.copy_transitions(1, &mut delta, &mut epsilon);
+        add_transition(&mut epsilon, 0, self.start + 1);
+
+        let mut accept = shift_states(&self.accept, 1);
+        accept.insert(0);
+
+        NFA {
+            accept: accept,
+            start: 0,
+            alphabet: self.alphabet.clone(),
+            delta: delta,
+            epsilon: epsilon,
+            num_states: self.num_states + 1,
+            labels: None
+        }
+    }
+
+    /// Returns an NFA accepting the reverse of every string self accepts.
+    ///
+    /// Every transition, including epsilon transitions, is reversed, and self's start state
+    /// becomes the only accept state. States keep their numbers and labels. A new start state,
+    /// numbered after all of them, has epsilon transitions to each of self's accept states.
+    pub fn reverse(&self) -> NFA<S> {
+        let new_start = self.num_states;
+        let mut delta = HashMap::new();
+        for (&(curr, ref sym), next) in self.delta.iter() {
+            for s in next.iter() {
+                add_transition(&mut delta, (s, sym.clone()), curr);
+            }
+        }
+
+        let mut epsilon = HashMap::new();
+        for (&curr, next) in self.epsilon.iter() {
+            for s in next.iter() {
+                add_transition(&mut epsilon, s, curr);
+            }
+        }
+
+        for s in self.accept.iter() {
+            add_transition(&mut epsilon, new_start, s);
+        }
+
+        let mut accept = BitvSet::new();
+        accept.insert(self.start);
+
+        NFA {
+            accept: accept,
+            start: new_start,
+            alphabet: self.alphabet.clone(),
+            delta: delta,
+            epsilon: epsilon,
+            num_states: self.num_states + 1,
+            labels: self.labels.clone()
+        }
+    }
+
+    /// Return true if no accept state can be reached, so self accepts no strings.
+    pub fn is_empty(&self) -> bool {
+        match self.longest_path() {
+            NoPath => true,
+            _ => false
+        }
+    }
+
+    /// Return true if self accepts every string over its alphabet.
+    ///
+    /// Checks that an NFA accepting every string is a subset of self using the antichain
+    /// algorithm of `is_subset_of`, so self is never fully determinized.
+    pub fn is_universal(&self) -> bool {
+        let transitions: Vec<Transition<S>> = self.alphabet.iter().map(|sym| (0u, sym.clone(), 0u)).collect();
+        let all = NFA::with_epsilons(1, &self.alphabet, &transitions, &vec!(), 0, &vec!(0)).ok().unwrap();
+        all.subset_witness(self).is_none()
+    }
+
+    /// Return true if self accepts only finitely many strings.
+    ///
+    /// The language is infinite exactly when a cycle that reads at least one symbol
+    /// passes through states that are reachable from the start state and can reach an accept state.
+    pub fn is_finite(&self) -> bool {
+        match self.longest_path() {
+            Unbounded => false,
+            _ => true
+        }
+    }
+
+    /// Returns the length of the longest string self accepts,
+    /// or None if self accepts no strings or infinitely many.
+    pub fn longest_accepted_length(&self) -> Option<uint> {
+        match self.longest_path() {
+            Finite(len) => Some(len),
+            NoPath | Unbounded => None
+        }
+    }
+
+    //Epsilon transitions read no symbols, so they add nothing to the length of a path
+    fn longest_path(&self) -> graph::PathLength {
+        let mut edges = vec!();
+        for (&(curr, _), next) in self.delta.iter() {
+            for n in next.iter() {
+                edges.push((curr, n, 1u));
+            }
+        }
+
+        for (&curr, next) in self.epsilon.iter() {
+            for n in next.iter() {
+                edges.push((curr, n, 0u));
+            }
+        }
+
+        graph::longest_path(self.num_states, &edges, self.start, &self.accept)
+    }
+
+    /// Returns an iterator over every string self accepts, in shortlex order.
+    ///
+    /// Strings are ordered by length, and strings of the same length by their symbols.
+    /// They are generated lazily by a breadth first search over the sets of states the NFA can be in,
+    /// skipping sets from which no accept state can be reached, so each string is produced once
+    /// and the iterator ends if the language is finite.
+    /// Use `take` on the iterator to bound the number of strings.
+    pub fn accepted_words<'a>(&'a self) -> AcceptedStrings<'a, S> {
+        AcceptedStrings::new(self, None)
+    }
+
+    /// Returns an iterator over every string of at most max_length symbols that self accepts,
+    /// in shortlex order.
+    pub fn accepted_words_up_to<'a>(&'a self, max_length: uint) -> AcceptedStrings<'a, S> {
+        AcceptedStrings::new(self, Some(max_length))
+    }
+
+    //The epsilon closure of the states reachable from any of states on sym
+    fn step(&self, states: &BitvSet, sym: &S) -> BitvSet {
+        let mut next = BitvSet::new();
+        for i in states.iter() {
+            match self.delta.find(&(i, sym.clone())) {
+                None => {},
+                Some(bv) => next.union_with(bv)
+            }
+        }
+
+        epsilons(&mut next, &self.epsilon);
+        next
+    }
+
+    //Returns a BitvSet containing all the states from which some accept state is reachable
+    fn coreachable_states(&self) -> BitvSet {
+        let mut coreachable = self.accept.clone();
+
+        loop {
+            let mut temp = BitvSet::new();
+            for (&(curr, _), next) in self.delta.iter() {
+                if !coreachable.contains(&curr) && next.iter().any(|x| coreachable.contains(&x)) {
+                    temp.insert(curr);
+                }
+            }
+
+            for (&curr, next) in self.epsilon.iter() {
+                if !coreachable.contains(&curr) && next.iter().any(|x| coreachable.contains(&x)) {
+                    temp.insert(curr);
+                }
+            }
+
+            if temp.is_empty() {
+                break;
+            }
+
+            coreachable.union_with(&temp);
+        }
+
+        coreachable
+    }
+
+    /// Returns a string accepted by self but not by other,
+    /// or None if every string self accepts is also accepted by other.
+    ///
+    /// Symbols outside of other's alphabet are rejected by other.
+    ///
+    /// Uses the antichain algorithm, so other is never fully determinized.
+    /// The search runs over pairs of a state of self and the set of states other could be in
+    /// after reading the same input. Reaching an accept state of self while other's set has no
+    /// accept state gives a counterexample. A pair is only explored if no pair with the same
+    /// state of self and a subset of its states has been found, as any counterexample
+    /// from the larger set is also one from the smaller set.
+    pub fn subset_witness(&self, other: &NFA<S>) -> Option<Vec<S>> {
+        let mut self_start = BitvSet::new();
+        self_start.insert(self.start);
+        epsilons(&mut self_start, &self.epsilon);
+
+        let mut other_start = BitvSet::new();
+        other_start.insert(other.start);
+        epsilons(&mut other_start, &other.epsilon);
+
+        //Each pair records the entry and symbol it was reached from to rebuild the witness.
+        //A pair stops being live once a pair with the same state and a smaller set is found.
+        //antichains.get(q) holds the live pairs with state q, so only those are compared.
+        let mut pairs: Vec<(uint, BitvSet, Option<(uint, S)>)> = vec!();
+        let mut live: Vec<bool> = vec!();
+        let mut antichains: Vec<Vec<uint>> = Vec::from_elem(self.num_states, vec!());
+        let mut queue = RingBuf::new();
+
+        for p in self_start.iter() {
+            pairs.push((p, other_start.clone(), None));
+            live.push(true);
+            antichains.get_mut(p).push(pairs.len() - 1);
+            queue.push_back(pairs.len() - 1);
+        }
+
+        loop {
+            let idx = match queue.pop_front() {
+                Some(i) => i,
+                None => return None
+            };
+
+            if !*live.get(idx) {
+                continue;
+            }
+
+            let (p, set) = {
+                let &(p, ref set, _) = pairs.get(idx);
+                (p, set.clone())
+            };
+
+            if self.accept.contains(&p) && !other.accept.iter().any(|x| set.contains(&x)) {
+                return Some(pair_witness(&pairs, idx));
+            }
+
+            for sym in self.alphabet.iter() {
+                let mut next_self = match self.delta.find(&(p, sym.clone())) {
+                    None => continue,
+                    Some(bv) => bv.clone()
+                };
+
+                epsilons(&mut next_self, &self.epsilon);
+
+                let next_set = other.step(&set, sym);
+
+                for q in next_self.iter() {
+                    let subsumed = antichains.get(q).iter().any(|&i| {
+                        let &(_, ref s, _) = pairs.get(i);
+                        s.is_subset(&next_set)
+                    });
+
+                    if subsumed {
+                        continue;
+                    }
+
+                    //Pairs with a larger set are dropped, though they are kept in pairs
+                    //as they may still be on the path to a witness
+                    antichains.get_mut(q).retain(|&i| {
+                        let &(_, ref s, _) = pairs.get(i);
+                        if next_set.is_subset(s) {
+                            *live.get_mut(i) = false;
+                            false
+                        }
+
+                        else {
+                            true
+                        }
+                    });
+
+                    pairs.push((q, next_set.clone(), Some((idx, sym.clone()))));
+                    live.push(true);
+                    antichains.get_mut(q).push(pairs.len() - 1);
+                    queue.push_back(pairs.len() - 1);
+                }
+            }
+        }
+    }
+
+    //Adds all of self's transitions to delta and epsilon, with every state renumbered by offset
+    fn copy_transitions(&self, offset: uint, delta: &mut HashMap<(uint, S), BitvSet>,
+                        epsilon: &mut HashMap<uint, BitvSet>) {
+        for (&(curr, ref sym), next) in self.delta.iter() {
+            for n in next.iter() {
+                add_transition(delta, (curr + offset, sym.clone()), n + offset);
+            }
+        }
+
+        for (&curr, next) in self.epsilon.iter() {
+            for n in next.iter() {
+                add_transition(epsilon, curr + offset, n + offset);
+            }
+        }
+    }
+}
+
+//Operations that take or produce strings, which are made of chars
+impl NFA<char> {
+    /// Builds an NFA.
+    ///
+    /// Transitions on '_' are epsilon transitions, so '_' cannot be in the alphabet.
+    /// Use `NFA::with_epsilons` for alphabets that contain '_'.
+    ///
+    /// Returns an Err if '_' is included in the alphabet, if the start or an accept
+    /// state does not exist, or if a transition contains a state or symbol that does not exist.
+    pub fn new(
+        num_states: uint,
+        alphabet: &Vec<char>,
+        transitions: &Vec<Transition>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> Result<NFA, AutomatonError> {
+
+        if alphabet.contains(&'_') {
+            return Err(ReservedSymbol('_'));
+        }
+
+        NFA::build(num_states, alphabet, underscores_written(transitions).move_iter(), start, accept,
+                   |t, state| underscore_out_of_range(t, state))
+    }
+
+    /// Checks a description of an NFA for every problem at once.
+    ///
+    /// Takes the same arguments as `NFA::new`, which succeeds exactly when the report has
+    /// no errors. Unreachable and dead states are reported as warnings.
+    pub fn validate(
+        num_states: uint,
+        alphabet: &Vec<char>,
+        transitions: &Vec<Transition>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> ValidationReport {
+
+        let mut report = ValidationReport { errors: vec!(), warnings: vec!() };
+        if alphabet.contains(&'_') {
+            report.errors.push(ReservedSymbol('_'));
+        }
+
+        NFA::check(report, num_states, alphabet, underscores_written(transitions).move_iter(), start, accept,
+                   |t, state| underscore_out_of_range(t, state))
+    }
+
+    /// Checks if every string self accepts is also accepted by other.
+    ///
+    /// Returns an Err holding a string accepted by self but not by other if self is not a subset.
+    /// See `subset_witness`.
+    pub fn is_subset_of(&self, other: &NFA) -> Result<(), String> {
+        match self.subset_witness(other) {
+            None => Ok(()),
+            Some(witness) => Err(witness.move_iter().collect())
+        }
+    }
+
+    /// Returns an iterator over every string self accepts, in shortlex order.
+    /// See `accepted_words`.
+    pub fn accepted_strings<'a>(&'a self) -> Strings<AcceptedStrings<'a, char>> {
+        Strings::new(self.accepted_words())
+    }
+
+    /// Returns an iterator over every string of at most max_length symbols that self accepts,
+    /// in shortlex order.
+    pub fn accepted_strings_up_to<'a>(&'a self, max_length: uint) -> Strings<AcceptedStrings<'a, char>> {
+        Strings::new(self.accepted_words_up_to(max_length))
+    }
+}
+
+/// Iterator over the strings an NFA accepts, in shortlex order.
+///
+/// Created by `NFA::accepted_words` and `NFA::accepted_words_up_to`.
+/// Each string is a Vec of symbols.
+pub struct AcceptedStrings<'a, S = char> {
+    nfa: &'a NFA<S>,
+    alphabet: Vec<S>,
+    live: BitvSet,
+    queue: RingBuf<(BitvSet, Vec<S>, uint)>,
+    max_length: Option<uint>
+}
+
+impl<'a, S: Symbol> AcceptedStrings<'a, S> {
+    fn new(nfa: &'a NFA<S>, max_length: Option<uint>) -> AcceptedStrings<'a, S> {
+        let mut alphabet = nfa.alphabet.clone();
+        alphabet.sort();
+
+        let live = nfa.coreachable_states();
+        let mut start = BitvSet::new();
+        start.insert(nfa.start);
+        epsilons(&mut start, &nfa.epsilon);
+
+        let mut queue = RingBuf::new();
+        if start.iter().any(|x| live.contains(&x)) {
+            queue.push_back((start, vec!(), 0u));
+        }
+
+        AcceptedStrings {
+            nfa: nfa,
+            alphabet: alphabet,
+            live: live,
+            queue: queue,
+            max_length: max_length
+        }
+    }
+}
+
+impl<'a, S: Symbol> Iterator<Vec<S>> for AcceptedStrings<'a, S> {
+    fn next(&mut self) -> Option<Vec<S>> {
+        loop {
+            let (states, word, len) = match self.queue.pop_front() {
+                Some(entry) => entry,
+                None => return None
+            };
+
+            let can_grow = match self.max_length {
+                Some(max) => len < max,
+                None => true
+            };
+
+            //Every queued set can still reach an accept state, so the queue only
+            //runs dry once every accepted string has been produced
+            if can_grow {
+                for sym in self.alphabet.iter() {
+                    let next = self.nfa.step(&states, sym);
+                    if next.iter().any(|x| self.live.contains(&x)) {
+                        let mut w = word.clone();
+                        w.push(sym.clone());
+                        self.queue.push_back((next, w, len + 1));
+                    }
+                }
+            }
+
+            if self.nfa.accept.iter().any(|x| states.contains(&x)) {
+                return Some(word);
+            }
+        }
+    }
+}
+
+//Transitions on symbols followed by epsilon transitions, as written to `NFA::with_epsilons`
+fn written<S: Symbol>(transitions: &Vec<Transition<S>>, epsilons: &Vec<(uint, uint)>) -> Vec<Written<S>> {
+    transitions.iter().map(|&(curr, ref sym, next)| (curr, Some(sym.clone()), next))
+               .chain(epsilons.iter().map(|&(curr, next)| (curr, None, next)))
+               .collect()
+}
+
+//Transitions written to `NFA::new`, where '_' marks an epsilon transition
+fn underscores_written(transitions: &Vec<Transition>) -> Vec<Written<char>> {
+    transitions.iter().map(|&(curr, sym, next)| (curr, if sym == '_' { None } else { Some(sym) }, next))
+               .collect()
+}
+
+//The error for a transition written to `NFA::new` on the given state, which does not exist
+fn underscore_out_of_range(t: &Written<char>, state: uint) -> AutomatonError {
+    let &(curr, sym, next) = t;
+    StateOutOfRange((curr, sym.unwrap_or('_'), next), state)
+}
+
+//Follows the chain of parents from pairs[idx] back to a start pair to rebuild the input that reached it
+fn pair_witness<S: Clone>(pairs: &Vec<(uint, BitvSet, Option<(uint, S)>)>, idx: uint) -> Vec<S> {
+    let mut syms = vec!();
+    let mut curr = idx;
+    loop {
+        match *pairs.get(curr) {
+            (_, _, Some((prev, ref sym))) => {
+                syms.push(sym.clone());
+                curr = prev;
+            },
+            (_, _, None) => break
+        }
+    }
+
+    syms.reverse();
+    syms
+}
+
+//Adds next to the set of states reachable by the transitions keyed by key,
+//either a (state, symbol) pair or a state for epsilon transitions
+fn add_transition<K: Hash + Eq>(delta: &mut HashMap<K, BitvSet>, key: K, next: uint) {
+    delta.find_with_or_insert_with(key, next,
+        //If the BitvSet exists, add next to it
+        |_, old, new| { old.insert(new); },
+
+        //If no match found, create a new BitvSet and add it
+        |_, v| {
+            let mut bv = BitvSet::new();
+            bv.insert(v);
+            bv }
+    );
+}
+
+//Returns a copy of states with every state renumbered by offset
+fn shift_states(states: &BitvSet, offset: uint) -> BitvSet {
+    let mut shifted = BitvSet::new();
+    for i in states.iter() {
+        shifted.insert(i + offset);
+    }
+
+    shifted
+}
+
+//Returns the symbols of a1 followed by any symbols of a2 that are not in a1
+fn merge_alphabets<S: Symbol>(a1: &Vec<S>, a2: &Vec<S>) -> Vec<S> {
+    let mut alphabet = a1.clone();
+    for sym in a2.iter() {
+        if !alphabet.contains(sym) {
+            alphabet.push(sym.clone());
+        }
+    }
+
+    alphabet
 }
 
 //In place expansion of the current states to include epsilon transitions.
 //It loops to handle the epsilon transitions from newly added states.
 //It terminates when no new states are added, so it will not get caught in epsilon cycles.
-fn epsilons(curr: &mut BitvSet, delta: &HashMap<(uint, char), BitvSet>) {
+fn epsilons(curr: &mut BitvSet, epsilon: &HashMap<uint, BitvSet>) {
     let mut next = BitvSet::new();
     loop {
         for i in curr.iter() {
-                match delta.find(&(i, '_')) {
+                match epsilon.find(&i) {
                     None => {},
                     Some(bv) => next.union_with(bv)
                 }
@@ -104,27 +890,42 @@ fn epsilons(curr: &mut BitvSet, delta: &HashMap<(uint, char), BitvSet>) {
     }
 }
 
-impl Run for NFA {
+//Written out because the transition maps are only Clone for hashable symbols
+impl<S: Symbol> Clone for NFA<S> {
+    fn clone(&self) -> NFA<S> {
+        NFA {
+            start: self.start,
+            alphabet: self.alphabet.clone(),
+            delta: self.delta.clone(),
+            epsilon: self.epsilon.clone(),
+            accept: self.accept.clone(),
+            num_states: self.num_states,
+            labels: self.labels.clone()
+        }
+    }
+}
+
+impl<S: Symbol> Run<S> for NFA<S> {
     // Check whether self accepts the given input string.
     // To do this, the string is run over the automaton starting from
     // the start state, similar to a DFA. However, instead of a single current state,
     // a vector of all the possible states it could be in is kept.
 
-    fn run(&self, input: &str) -> Option<bool> {
+    fn run<I: Word<S>>(&self, input: I) -> Option<bool> {
         let mut curr_states = BitvSet::new();
         let mut next_states = BitvSet::new();
 
         curr_states.insert(self.start);
-        epsilons(&mut curr_states, &self.delta);
+        epsilons(&mut curr_states, &self.epsilon);
 
-        for sym in input.chars() {
+        for sym in input.symbols().move_iter() {
             if !self.alphabet.contains(&sym) {
                 return None;
             }
 
             //Get transitions from the current input symbol
             for i in curr_states.iter() {
-                match self.delta.find(&(i, sym)) {
+                match self.delta.find(&(i, sym.clone())) {
                     None => {},
                     Some(bv) => next_states.union_with(bv)
                 }
@@ -139,30 +940,46 @@ impl Run for NFA {
             curr_states.union_with(&next_states);
             next_states.clear();
 
-            epsilons(&mut curr_states, &self.delta);
+            epsilons(&mut curr_states, &self.epsilon);
         }
 
         Some(self.accept.iter().any(|x| curr_states.contains(&x)))
     }
 }
 
-impl fmt::Show for NFA {
+impl<S: Symbol + fmt::Show> fmt::Show for NFA<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(f, "Alphabet: {}\n", self.alphabet));
-        try!(write!(f, "Start State: {}\n", self.start));
-        try!(write!(f, "Accept States: {}\n", self.accept));
+        try!(write!(f, "Start State: {}\n", self.state_label(self.start)));
+        match self.labels {
+            None => try!(write!(f, "Accept States: {}\n", self.accept)),
+            Some(_) => try!(write!(f, "Accept States: {}\n", self.state_labels(&self.accept)))
+        }
         try!(write!(f, "Transitions: \n"));
 
+        //Epsilon transitions have no symbol, so they sort before the others from the same state
         let mut temp = vec!();
-        for &(curr, sym) in self.delta.keys() {
-            temp.push((curr, sym));
+        for &(curr, ref sym) in self.delta.keys() {
+            temp.push((curr, Some(sym.clone())));
+        }
+
+        for &curr in self.epsilon.keys() {
+            temp.push((curr, None));
         }
 
         temp.sort();
 
-        for &(curr, sym) in temp.iter() {
-            let next = self.delta.get(&(curr, sym));
-            try!(write!(f, "  ({}, '{}') -> {}\n", curr, sym, next));
+        for &(curr, ref sym) in temp.iter() {
+            let (label, next) = match *sym {
+                Some(ref c) => (format!("'{}'", c), self.delta.get(&(curr, c.clone()))),
+                None => ("ε".to_string(), self.epsilon.get(&curr))
+            };
+
+            match self.labels {
+                None => try!(write!(f, "  ({}, {}) -> {}\n", curr, label, next)),
+                Some(_) => try!(write!(f, "  ({}, {}) -> {}\n", self.state_label(curr), label,
+                                       self.state_labels(next)))
+            }
         }
         Ok(())
     }
@@ -178,7 +995,7 @@ mod tests {
 
     #[test]
     fn computes_all_epsilons() {
-        let mut hash: HashMap<(uint, char), BitvSet> = HashMap::new();
+        let mut hash: HashMap<uint, BitvSet> = HashMap::new();
         let mut curr = BitvSet::new();
         let mut expected = BitvSet::new();
 
@@ -190,7 +1007,7 @@ mod tests {
 
         let trns = vec!((1,2), (1,0), (0, 3), (2, 4), (5, 6));
         for &(k, v) in trns.iter() {
-            hash.find_with_or_insert_with((k, '_'), v,
+            hash.find_with_or_insert_with(k, v,
                 |_, old, new| { old.insert(new); }, 
                 |_, v| {
                     let mut bv = BitvSet::new();
@@ -0,0 +1,433 @@
+use std::char;
+use std::cmp;
+use std::cmp::{Less, Equal, Greater};
+use std::collections::hashmap::HashMap;
+use std::fmt;
+use super::{DFA, Run, Word, RangeTransition};
+use super::error::{AutomatonError, NoStates, TooManyStates, TransitionCount, UnknownSymbol};
+use super::error::{StateOutOfRange, EpsilonOutOfRange, DuplicateTransition, MissingTransition};
+use super::error::{ReservedSymbol, InvalidRange, InvalidStart, InvalidAccept};
+use super::error::{UnknownState, NoStart, MultipleStarts};
+
+/// A DFA over chars whose transitions read ranges of chars.
+///
+/// The alphabet is split into classes of chars that every transition treats the same way,
+/// and the automaton runs on those classes instead of on single chars. Its size depends on
+/// the number of distinct ranges used, not on the number of chars in the alphabet,
+/// so it can work over all of ASCII or Unicode.
+#[deriving(Clone)]
+pub struct RangeDFA {
+    //Disjoint ranges covering the alphabet in order, each with the number of its class
+    ranges: Vec<(char, char, uint)>,
+    //A DFA over class numbers
+    dfa: DFA<uint>
+}
+
+impl RangeDFA {
+    /// Creates a new DFA whose transitions read ranges of chars.
+    ///
+    /// The alphabet is given as a list of ranges. Ranges include both ends, so `('a', 'z')`
+    /// is every lowercase letter. Each char of the alphabet must be in exactly one range
+    /// of the transitions from each state.
+    ///
+    /// Returns an Err if a range starts after it ends, if a transition reads a char that is not
+    /// in the alphabet, if the ranges of two transitions from a state overlap, or if a char
+    /// has no transition from some state. Errors about a range name its first char with the problem.
+    pub fn new(
+        num_states: uint,
+        alphabet: &Vec<(char, char)>,
+        transitions: &Vec<RangeTransition>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> Result<RangeDFA, AutomatonError> {
+
+        let dfa = try!(RangeDFA::new_partial(num_states, alphabet, transitions, start, accept));
+        for state in range(0, num_states) {
+            for &(lo, _, class) in dfa.ranges.iter() {
+                if dfa.dfa.next_state(state, &class).is_none() {
+                    return Err(MissingTransition(state, lo));
+                }
+            }
+        }
+
+        Ok(dfa)
+    }
+
+    /// Creates a new partial DFA whose transitions read ranges of chars.
+    ///
+    /// Chars without a transition go to an implicit dead state, as in `DFA::new_partial`.
+    /// Returns an Err in the same cases as `RangeDFA::new`, except for missing transitions.
+    pub fn new_partial(
+        num_states: uint,
+        alphabet: &Vec<(char, char)>,
+        transitions: &Vec<RangeTransition>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> Result<RangeDFA, AutomatonError> {
+
+        let mut cuts = vec!();
+        for &(lo, hi) in alphabet.iter() {
+            if lo > hi {
+                return Err(InvalidRange(lo, hi));
+            }
+        }
+
+        for &(_, (lo, hi), _) in transitions.iter() {
+            if lo > hi {
+                return Err(InvalidRange(lo, hi));
+            }
+
+            cuts.push(lo);
+            match next_char(hi) {
+                Some(c) => cuts.push(c),
+                None => {}
+            }
+        }
+
+        //Cutting the alphabet at the ends of every range leaves pieces that no transition splits,
+        //which start out as the classes
+        let mut ranges = vec!();
+        for (class, &(lo, hi)) in split(&merge_ranges(alphabet), cuts).iter().enumerate() {
+            ranges.push((lo, hi, class));
+        }
+
+        let mut class_transitions = vec!();
+        for &(curr, (lo, hi), next) in transitions.iter() {
+            //The range of the transition is made of consecutive pieces
+            let mut c = lo;
+            loop {
+                let (piece_hi, class) = match find_range(&ranges, c) {
+                    Some(idx) => {
+                        let &(_, piece_hi, class) = ranges.get(idx);
+                        (piece_hi, class)
+                    },
+                    None => return Err(UnknownSymbol((curr, c, next)))
+                };
+
+                class_transitions.push((curr, class, next));
+                if piece_hi >= hi {
+                    break;
+                }
+
+                c = next_char(piece_hi).unwrap();
+            }
+        }
+
+        let classes: Vec<uint> = range(0, ranges.len()).collect();
+        match DFA::new_partial(num_states, &classes, &class_transitions, start, accept) {
+            Ok(dfa) => Ok(RangeDFA { ranges: ranges, dfa: dfa }),
+            Err(e) => Err(class_error(&ranges, e))
+        }
+    }
+
+    /// Returns the number of states.
+    pub fn num_states(&self) -> uint {
+        self.dfa.num_states()
+    }
+
+    /// Returns the number of classes the alphabet is split into.
+    pub fn num_classes(&self) -> uint {
+        self.ranges.iter().fold(0, |n, &(_, _, class)| cmp::max(n, class + 1))
+    }
+
+    /// Returns a DFA accepting the strings accepted by self or other.
+    ///
+    /// The alphabet of the result is the union of both alphabets. A char outside of the alphabet
+    /// of one DFA is treated as leading to a rejecting dead state of that DFA.
+    pub fn union(&self, other: &RangeDFA) -> RangeDFA {
+        self.product(other, |x, y| { x || y })
+    }
+
+    /// Returns a DFA accepting the strings accepted by both self and other.
+    pub fn intersect(&self, other: &RangeDFA) -> RangeDFA {
+        self.product(other, |x, y| { x && y })
+    }
+
+    /// Returns a DFA accepting the strings accepted by self but not by other.
+    pub fn difference(&self, other: &RangeDFA) -> RangeDFA {
+        self.product(other, |x, y| { x && !y })
+    }
+
+    /// Returns a DFA accepting the strings accepted by exactly one of self and other.
+    pub fn symmetric_difference(&self, other: &RangeDFA) -> RangeDFA {
+        self.product(other, |x, y| { x != y })
+    }
+
+    //Splits the classes of both DFAs so that each new class is within one class of each,
+    //then takes the product of both DFAs over the new classes
+    fn product(&self, other: &RangeDFA, f: |bool, bool| -> bool) -> RangeDFA {
+        let mut alphabet = vec!();
+        let mut cuts = vec!();
+        for &(lo, hi, _) in self.ranges.iter().chain(other.ranges.iter()) {
+            alphabet.push((lo, hi));
+            cuts.push(lo);
+            match next_char(hi) {
+                Some(c) => cuts.push(c),
+                None => {}
+            }
+        }
+
+        //Each pair of classes that share some char becomes a new class
+        let mut index = HashMap::new();
+        let mut pairs: Vec<(Option<uint>, Option<uint>)> = vec!();
+        let mut ranges = vec!();
+        for &(lo, hi) in split(&merge_ranges(&alphabet), cuts).iter() {
+            let pair = (self.class_of(lo), other.class_of(lo));
+            let class = match index.find_copy(&pair) {
+                Some(class) => class,
+                None => {
+                    index.insert(pair, pairs.len());
+                    pairs.push(pair);
+                    pairs.len() - 1
+                }
+            };
+
+            push_range(&mut ranges, lo, hi, class);
+        }
+
+        let classes: Vec<uint> = range(0, pairs.len()).collect();
+        let d1 = self.dfa.inverse_image(&classes, |&class| { let (c, _) = *pairs.get(class); c });
+        let d2 = other.dfa.inverse_image(&classes, |&class| { let (_, c) = *pairs.get(class); c });
+
+        RangeDFA { ranges: ranges, dfa: d1.product_with(&d2, f) }
+    }
+
+    /// Returns a DFA accepting every string over self's alphabet that self rejects.
+    pub fn complement(&self) -> RangeDFA {
+        RangeDFA { ranges: self.ranges.clone(), dfa: self.dfa.complement() }
+    }
+
+    /// Reduces the number of states and classes in-place to the minimum necessary to
+    /// recognize the same language.
+    ///
+    /// The states are minimized with `DFA::minimize`. Then classes whose chars lead from every
+    /// state to the same next state are merged, so a class can be made of several ranges.
+    pub fn minimize(&mut self) {
+        self.dfa.minimize();
+
+        //A class is known by the states it leads to from each state
+        let num_states = self.dfa.num_states();
+        let mut columns = HashMap::new();
+        let mut merged = vec!();
+        let mut kept = vec!();
+        for class in range(0, self.num_classes()) {
+            let column: Vec<Option<uint>> = range(0, num_states).map(|s| self.dfa.next_state(s, &class)).collect();
+            let new_class = match columns.find_copy(&column) {
+                Some(c) => c,
+                None => {
+                    columns.insert(column, kept.len());
+                    kept.push(class);
+                    kept.len() - 1
+                }
+            };
+
+            merged.push(new_class);
+        }
+
+        let classes: Vec<uint> = range(0, kept.len()).collect();
+        self.dfa = self.dfa.inverse_image(&classes, |&class| Some(*kept.get(class)));
+
+        let mut ranges = vec!();
+        for &(lo, hi, class) in self.ranges.iter() {
+            push_range(&mut ranges, lo, hi, *merged.get(class));
+        }
+
+        self.ranges = ranges;
+    }
+
+    /// Checks if self and other accept the same strings.
+    pub fn equivalent(&self, other: &RangeDFA) -> bool {
+        self.symmetric_difference(other).is_empty()
+    }
+
+    /// Return true if self accepts no strings.
+    pub fn is_empty(&self) -> bool {
+        self.dfa.is_empty()
+    }
+
+    /// Returns an equivalent DFA with a symbol for every char of the alphabet.
+    pub fn to_dfa(&self) -> DFA {
+        let mut alphabet = vec!();
+        let mut classes = HashMap::new();
+        for &(lo, hi, class) in self.ranges.iter() {
+            let mut c = lo;
+            loop {
+                alphabet.push(c);
+                classes.insert(c, class);
+                if c == hi {
+                    break;
+                }
+
+                c = next_char(c).unwrap();
+            }
+        }
+
+        self.dfa.inverse_image(&alphabet, |c| classes.find_copy(c))
+    }
+
+    //The class of c, or None if c is not in the alphabet
+    fn class_of(&self, c: char) -> Option<uint> {
+        find_range(&self.ranges, c).map(|idx| {
+            let &(_, _, class) = self.ranges.get(idx);
+            class
+        })
+    }
+}
+
+impl Run for RangeDFA {
+    fn run<I: Word<char>>(&self, input: I) -> Option<bool> {
+        let mut classes = vec!();
+        for c in input.symbols().move_iter() {
+            match self.class_of(c) {
+                Some(class) => classes.push(class),
+                None => return None
+            }
+        }
+
+        self.dfa.run(classes.as_slice())
+    }
+}
+
+impl fmt::Show for RangeDFA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "Classes:\n"));
+        for class in range(0, self.num_classes()) {
+            try!(write!(f, "  {}:", class));
+            for &(lo, hi, _) in self.ranges.iter().filter(|&&(_, _, c)| c == class) {
+                if lo == hi {
+                    try!(write!(f, " '{}'", lo));
+                }
+
+                else {
+                    try!(write!(f, " '{}'-'{}'", lo, hi));
+                }
+            }
+
+            try!(write!(f, "\n"));
+        }
+
+        write!(f, "{}", self.dfa)
+    }
+}
+
+//Restates an error about classes as one about the first char of each class
+fn class_error(ranges: &Vec<(char, char, uint)>, e: AutomatonError<uint>) -> AutomatonError {
+    //Every class in an error comes from ranges, so the fallback is never used
+    let first = |class: uint| -> char {
+        ranges.iter().find(|&&(_, _, c)| c == class).map(|&(lo, _, _)| lo).unwrap_or('\0')
+    };
+
+    match e {
+        NoStates => NoStates,
+        TooManyStates(count) => TooManyStates(count),
+        TransitionCount(given, expected) => TransitionCount(given, expected),
+        UnknownSymbol((curr, class, next)) => UnknownSymbol((curr, first(class), next)),
+        StateOutOfRange((curr, class, next), s) => StateOutOfRange((curr, first(class), next), s),
+        EpsilonOutOfRange(t, s) => EpsilonOutOfRange(t, s),
+        DuplicateTransition((curr, class, next)) => DuplicateTransition((curr, first(class), next)),
+        MissingTransition(s, class) => MissingTransition(s, first(class)),
+        ReservedSymbol(class) => ReservedSymbol(first(class)),
+        InvalidRange(lo, hi) => InvalidRange(first(lo), first(hi)),
+        InvalidStart(s) => InvalidStart(s),
+        InvalidAccept(s) => InvalidAccept(s),
+        UnknownState(label) => UnknownState(label),
+        NoStart => NoStart,
+        MultipleStarts(first_label, second_label) => MultipleStarts(first_label, second_label)
+    }
+}
+
+//The char after c, skipping surrogates, or None if c is the last char
+pub fn next_char(c: char) -> Option<char> {
+    match c as u32 {
+        0xD7FF => Some('\uE000'),
+        n => char::from_u32(n + 1)
+    }
+}
+
+//The char before c, skipping surrogates, or None if c is the first char
+pub fn prev_char(c: char) -> Option<char> {
+    match c as u32 {
+        0 => None,
+        0xE000 => Some('\uD7FF'),
+        n => char::from_u32(n - 1)
+    }
+}
+
+//Sorts ranges and joins the ones that overlap or touch
+pub fn merge_ranges(ranges: &Vec<(char, char)>) -> Vec<(char, char)> {
+    let mut sorted = ranges.clone();
+    sorted.sort();
+
+    let mut merged: Vec<(char, char)> = vec!();
+    for &(lo, hi) in sorted.iter() {
+        let n = merged.len();
+        if n > 0 {
+            let (last_lo, last_hi) = *merged.get(n - 1);
+            if next_char(last_hi).map_or(true, |c| lo <= c) {
+                *merged.get_mut(n - 1) = (last_lo, cmp::max(hi, last_hi));
+                continue;
+            }
+        }
+
+        merged.push((lo, hi));
+    }
+
+    merged
+}
+
+//Splits sorted, disjoint ranges at every cut inside them, where a cut is the first char of a piece
+fn split(ranges: &Vec<(char, char)>, mut cuts: Vec<char>) -> Vec<(char, char)> {
+    cuts.sort();
+    cuts.dedup();
+
+    let mut pieces = vec!();
+    let mut i = 0;
+    for &(lo, hi) in ranges.iter() {
+        while i < cuts.len() && *cuts.get(i) <= lo {
+            i += 1;
+        }
+
+        let mut start = lo;
+        while i < cuts.len() && *cuts.get(i) <= hi {
+            let cut = *cuts.get(i);
+            pieces.push((start, prev_char(cut).unwrap()));
+            start = cut;
+            i += 1;
+        }
+
+        pieces.push((start, hi));
+    }
+
+    pieces
+}
+
+//Adds a range after the others, joining it to the last range if they touch and have the same class
+fn push_range(ranges: &mut Vec<(char, char, uint)>, lo: char, hi: char, class: uint) {
+    let n = ranges.len();
+    if n > 0 {
+        let (last_lo, last_hi, last_class) = *ranges.get(n - 1);
+        if last_class == class && next_char(last_hi) == Some(lo) {
+            *ranges.get_mut(n - 1) = (last_lo, hi, class);
+            return;
+        }
+    }
+
+    ranges.push((lo, hi, class));
+}
+
+//The index of the range containing c in sorted, disjoint ranges
+fn find_range(ranges: &Vec<(char, char, uint)>, c: char) -> Option<uint> {
+    ranges.as_slice().bsearch(|&(lo, hi, _)| {
+        if hi < c {
+            Less
+        }
+
+        else if lo > c {
+            Greater
+        }
+
+        else {
+            Equal
+        }
+    })
+}
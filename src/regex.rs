@@ -0,0 +1,602 @@
+use std::fmt;
+use super::{NFA, Transition, AutomatonError};
+
+/// Regular expression over an explicitly given alphabet.
+///
+/// Expressions are usually built with `Regex::parse`, which accepts the syntax:
+///
+/// * `r|s` matches either r or s (lowest precedence)
+/// * `rs` matches r followed by s
+/// * `r*`, `r+` and `r?` match zero or more, one or more, and zero or one r
+/// * `(r)` groups an expression
+/// * `[abc]` and `[a-c]` match one symbol of a set, and `[^abc]` matches any symbol
+///   of the alphabet that is not in the set
+/// * `.` matches any one symbol of the alphabet
+/// * `ε` matches only the empty string and `∅` matches nothing
+/// * `\` escapes the next character, so `\*` matches the symbol `*`
+#[deriving(Clone, PartialEq)]
+pub enum Regex {
+    /// The empty language `∅`
+    Empty,
+    /// The language containing only the empty string, `ε`
+    Epsilon,
+    /// A single symbol
+    Literal(char),
+    /// Any one symbol from a set of symbols
+    Class(Vec<char>),
+    /// The first expression followed by the second
+    Concat(Box<Regex>, Box<Regex>),
+    /// Either of two expressions
+    Alternate(Box<Regex>, Box<Regex>),
+    /// Zero or more repetitions of an expression
+    Star(Box<Regex>),
+    /// One or more repetitions of an expression
+    Plus(Box<Regex>),
+    /// Zero or one occurrences of an expression
+    Optional(Box<Regex>)
+}
+
+/// Heuristic for choosing which state to remove next when converting a DFA
+/// to a regular expression by state elimination.
+///
+/// The order does not change the language of the result, only how long it is.
+#[deriving(Clone, PartialEq, Show)]
+pub enum EliminationOrder {
+    /// Remove states in increasing numerical order
+    InOrder,
+    /// Remove the state with the fewest paths passing through it,
+    /// that is the smallest product of incoming and outgoing edges
+    FewestPaths,
+    /// Remove the state whose elimination adds the least to the size of the expression.
+    /// This is the weight heuristic of Delgado and Morais.
+    LeastWeight
+}
+
+/// An error found while parsing a regular expression.
+#[deriving(Clone, PartialEq)]
+pub struct ParseError {
+    /// Index of the character in the pattern where the error occurred
+    pub position: uint,
+    /// Description of the error
+    pub message: String
+}
+
+impl fmt::Show for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "At position {}: {}", self.position, self.message)
+    }
+}
+
+impl Regex {
+    /// Parses a regular expression over the given alphabet.
+    ///
+    /// An empty expression, such as either side of `a|` or the inside of `()`,
+    /// is treated as `ε`.
+    ///
+    /// Returns an Err holding the position of the problem if the pattern is malformed or
+    /// uses a symbol that is not in the alphabet.
+    pub fn parse(pattern: &str, alphabet: &Vec<char>) -> Result<Regex, ParseError> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            alphabet: alphabet
+        };
+
+        let regex = try!(parser.alternation());
+
+        //alternation only stops early on a closing paren that has no match
+        match parser.peek() {
+            None => Ok(regex),
+            Some(c) => parser.error_at(parser.pos, format!("Unmatched `{}`", c))
+        }
+    }
+
+    /// Builds an NFA over alphabet recognizing the same language as the expression.
+    ///
+    /// Uses Thompson's construction on top of the NFA combinators.
+    /// Returns an Err if the expression uses a symbol that is not in the alphabet.
+    pub fn to_nfa(&self, alphabet: &Vec<char>) -> Result<NFA, AutomatonError> {
+        let nfa = match *self {
+            Empty => try!(NFA::with_epsilons(1, alphabet, &vec!(), &vec!(), 0, &vec!())),
+            Epsilon => try!(NFA::with_epsilons(1, alphabet, &vec!(), &vec!(), 0, &vec!(0))),
+            Literal(c) => try!(symbols_nfa(&vec!(c), alphabet)),
+            Class(ref syms) => try!(symbols_nfa(syms, alphabet)),
+            Concat(ref r, ref s) => try!(r.to_nfa(alphabet)).concat(&try!(s.to_nfa(alphabet))),
+            Alternate(ref r, ref s) => try!(r.to_nfa(alphabet)).union(&try!(s.to_nfa(alphabet))),
+            Star(ref r) => try!(r.to_nfa(alphabet)).star(),
+            Plus(ref r) => try!(r.to_nfa(alphabet)).plus(),
+            Optional(ref r) => try!(r.to_nfa(alphabet)).optional()
+        };
+
+        Ok(nfa)
+    }
+
+    /// Returns an expression for `r|s`.
+    ///
+    /// Simplifies using `∅|r = r`, `r|r = r`, `ε|r* = r*` and `ε|r = r?`, and merges
+    /// alternatives of single symbols into a class.
+    pub fn alternate(r: Regex, s: Regex) -> Regex {
+        match (r, s) {
+            (Empty, x) | (x, Empty) => x,
+            (r, s) => {
+                if r == s || r.has_alternative(&s) {
+                    return r;
+                }
+
+                match (r.symbols(), s.symbols()) {
+                    (Some(mut syms), Some(other)) => {
+                        syms.push_all(other.as_slice());
+                        return class_of(syms);
+                    },
+                    _ => {}
+                }
+
+                match (r, s) {
+                    (Epsilon, Star(x)) | (Star(x), Epsilon) => Star(x),
+                    (Epsilon, Plus(x)) | (Plus(x), Epsilon) => Star(x),
+                    (Epsilon, x) | (x, Epsilon) => Regex::optional(x),
+                    (r, s) => Alternate(box r, box s)
+                }
+            }
+        }
+    }
+
+    /// Returns an expression for `rs`.
+    ///
+    /// Simplifies using `∅r = r∅ = ∅`, `εr = rε = r` and `rr* = r*r = r+`.
+    pub fn concat(r: Regex, s: Regex) -> Regex {
+        match (r, s) {
+            (Empty, _) | (_, Empty) => Empty,
+            (Epsilon, x) | (x, Epsilon) => x,
+            (r, s) => {
+                let star_first = match r { Star(ref x) => **x == s, _ => false };
+                let star_second = match s { Star(ref x) => **x == r, _ => false };
+
+                if star_first {
+                    match r { Star(x) => Plus(x), _ => unreachable!() }
+                }
+
+                else if star_second {
+                    match s { Star(x) => Plus(x), _ => unreachable!() }
+                }
+
+                else {
+                    Concat(box r, box s)
+                }
+            }
+        }
+    }
+
+    /// Returns an expression for `r*`.
+    ///
+    /// Simplifies using `∅* = ε* = ε` and `r** = r+* = r?* = r*`.
+    pub fn star(r: Regex) -> Regex {
+        match r {
+            Empty | Epsilon => Epsilon,
+            Star(x) | Plus(x) | Optional(x) => Star(x),
+            r => Star(box r)
+        }
+    }
+
+    /// Returns an expression for `r+`.
+    ///
+    /// Simplifies using `∅+ = ∅`, `ε+ = ε`, `r*+ = r?+ = r*` and `r++ = r+`.
+    pub fn plus(r: Regex) -> Regex {
+        match r {
+            Empty => Empty,
+            Epsilon => Epsilon,
+            Star(x) | Optional(x) => Star(x),
+            Plus(x) => Plus(x),
+            r => Plus(box r)
+        }
+    }
+
+    /// Returns an expression for `r?`.
+    ///
+    /// Simplifies using `∅? = ε? = ε`, `r*? = r+? = r*` and `r?? = r?`.
+    pub fn optional(r: Regex) -> Regex {
+        match r {
+            Empty | Epsilon => Epsilon,
+            Star(x) | Plus(x) => Star(x),
+            Optional(x) => Optional(x),
+            r => Optional(box r)
+        }
+    }
+
+    /// Returns an equivalent expression, rebuilt bottom up with the
+    /// simplifications applied by `alternate`, `concat`, `star`, `plus` and `optional`.
+    pub fn simplify(&self) -> Regex {
+        match *self {
+            Class(ref syms) => class_of(syms.clone()),
+            Concat(ref r, ref s) => Regex::concat(r.simplify(), s.simplify()),
+            Alternate(ref r, ref s) => Regex::alternate(r.simplify(), s.simplify()),
+            Star(ref r) => Regex::star(r.simplify()),
+            Plus(ref r) => Regex::plus(r.simplify()),
+            Optional(ref r) => Regex::optional(r.simplify()),
+            ref r => r.clone()
+        }
+    }
+
+    /// Returns the number of symbols, classes and operators in the expression.
+    pub fn size(&self) -> uint {
+        match *self {
+            Empty | Epsilon | Literal(_) | Class(_) => 1,
+            Concat(ref r, ref s) | Alternate(ref r, ref s) => 1 + r.size() + s.size(),
+            Star(ref r) | Plus(ref r) | Optional(ref r) => 1 + r.size()
+        }
+    }
+
+    //The symbols matched if self is a single symbol or class
+    fn symbols(&self) -> Option<Vec<char>> {
+        match *self {
+            Literal(c) => Some(vec!(c)),
+            Class(ref syms) => Some(syms.clone()),
+            _ => None
+        }
+    }
+
+    //Check if r is one of the alternatives of self
+    fn has_alternative(&self, r: &Regex) -> bool {
+        match *self {
+            Alternate(ref a, ref b) => {
+                **a == *r || **b == *r || a.has_alternative(r) || b.has_alternative(r)
+            },
+            _ => false
+        }
+    }
+
+    //Writes self, wrapping it in parentheses if it binds looser than prec.
+    //Alternation is level 0, concatenation level 1, and the operand of a repetition level 2.
+    fn write(&self, f: &mut fmt::Formatter, prec: uint) -> fmt::Result {
+        match *self {
+            Empty => write!(f, "∅"),
+            Epsilon => write!(f, "ε"),
+            Literal(c) => write_symbol(f, c, SPECIAL),
+            Class(ref syms) => write_class(f, syms),
+
+            Alternate(ref r, ref s) => {
+                if prec > 0 {
+                    try!(write!(f, "("));
+                }
+
+                try!(r.write(f, 0));
+                try!(write!(f, "|"));
+                try!(s.write(f, 0));
+
+                if prec > 0 {
+                    try!(write!(f, ")"));
+                }
+
+                Ok(())
+            },
+
+            Concat(ref r, ref s) => {
+                if prec > 1 {
+                    try!(write!(f, "("));
+                }
+
+                try!(r.write(f, 1));
+                try!(s.write(f, 1));
+
+                if prec > 1 {
+                    try!(write!(f, ")"));
+                }
+
+                Ok(())
+            },
+
+            Star(ref r) => {
+                try!(r.write(f, 2));
+                write!(f, "*")
+            },
+
+            Plus(ref r) => {
+                try!(r.write(f, 2));
+                write!(f, "+")
+            },
+
+            Optional(ref r) => {
+                try!(r.write(f, 2));
+                write!(f, "?")
+            }
+        }
+    }
+}
+
+/// Prints the expression in the syntax accepted by `Regex::parse`,
+/// using as few parentheses as possible.
+impl fmt::Show for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+//Characters that must be escaped outside and inside of a class
+static SPECIAL: &'static str = "|*+?()[]\\.ε∅";
+static CLASS_SPECIAL: &'static str = "]\\^-";
+
+fn write_symbol(f: &mut fmt::Formatter, c: char, special: &str) -> fmt::Result {
+    if special.contains_char(c) {
+        write!(f, "\\{}", c)
+    }
+
+    else {
+        write!(f, "{}", c)
+    }
+}
+
+//Writes a class, collapsing runs of three or more consecutive characters into a range
+fn write_class(f: &mut fmt::Formatter, syms: &Vec<char>) -> fmt::Result {
+    match syms.len() {
+        0 => return write!(f, "∅"),
+        1 => return write_symbol(f, *syms.get(0), SPECIAL),
+        _ => {}
+    }
+
+    let mut sorted = syms.clone();
+    sorted.sort();
+    sorted.dedup();
+
+    try!(write!(f, "["));
+
+    let mut i = 0;
+    while i < sorted.len() {
+        let lo = *sorted.get(i);
+        let mut j = i;
+        while j + 1 < sorted.len() && *sorted.get(j + 1) as u32 == *sorted.get(j) as u32 + 1 {
+            j += 1;
+        }
+
+        try!(write_symbol(f, lo, CLASS_SPECIAL));
+        if j >= i + 2 {
+            try!(write!(f, "-"));
+            try!(write_symbol(f, *sorted.get(j), CLASS_SPECIAL));
+        }
+
+        else {
+            j = i;
+        }
+
+        i = j + 1;
+    }
+
+    write!(f, "]")
+}
+
+//Builds the simplest expression matching exactly one of syms
+fn class_of(mut syms: Vec<char>) -> Regex {
+    syms.sort();
+    syms.dedup();
+
+    match syms.len() {
+        0 => Empty,
+        1 => Literal(*syms.get(0)),
+        _ => Class(syms)
+    }
+}
+
+/// Parses pattern and compiles it to an NFA over alphabet.
+///
+/// See `Regex` for the accepted syntax. If the NFA cannot be built over alphabet, the
+/// `AutomatonError` is returned as the message of a ParseError at position 0.
+pub fn compile(pattern: &str, alphabet: &Vec<char>) -> Result<NFA, ParseError> {
+    let regex = try!(Regex::parse(pattern, alphabet));
+
+    regex.to_nfa(alphabet).map_err(|e| ParseError { position: 0, message: format!("{}", e) })
+}
+
+//Two state NFA moving from its start state to its accept state on any of symbols
+fn symbols_nfa(symbols: &Vec<char>, alphabet: &Vec<char>) -> Result<NFA, AutomatonError> {
+    let transitions: Vec<Transition> = symbols.iter().map(|&sym| (0u, sym, 1u)).collect();
+    NFA::with_epsilons(2, alphabet, &transitions, &vec!(), 0, &vec!(1))
+}
+
+//Recursive descent parser. Each method parses one level of precedence.
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: uint,
+    alphabet: &'a Vec<char>
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        if self.pos < self.chars.len() {
+            Some(*self.chars.get(self.pos))
+        }
+
+        else {
+            None
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+
+        c
+    }
+
+    fn error_at<T>(&self, position: uint, message: String) -> Result<T, ParseError> {
+        Err(ParseError { position: position, message: message })
+    }
+
+    //alternation := concatenation ('|' concatenation)*
+    fn alternation(&mut self) -> Result<Regex, ParseError> {
+        let mut regex = try!(self.concatenation());
+
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            let rhs = try!(self.concatenation());
+            regex = Alternate(box regex, box rhs);
+        }
+
+        Ok(regex)
+    }
+
+    //concatenation := repetition*
+    fn concatenation(&mut self) -> Result<Regex, ParseError> {
+        let mut regex = None;
+
+        loop {
+            match self.peek() {
+                None | Some('|') | Some(')') => break,
+                _ => {}
+            }
+
+            let rhs = try!(self.repetition());
+            regex = match regex {
+                None => Some(rhs),
+                Some(lhs) => Some(Concat(box lhs, box rhs))
+            };
+        }
+
+        Ok(regex.unwrap_or(Epsilon))
+    }
+
+    //repetition := atom ('*' | '+' | '?')*
+    fn repetition(&mut self) -> Result<Regex, ParseError> {
+        let mut regex = try!(self.atom());
+
+        loop {
+            regex = match self.peek() {
+                Some('*') => Star(box regex),
+                Some('+') => Plus(box regex),
+                Some('?') => Optional(box regex),
+                _ => break
+            };
+
+            self.pos += 1;
+        }
+
+        Ok(regex)
+    }
+
+    //atom := symbol | '\' char | '.' | 'ε' | '∅' | '(' alternation ')' | class
+    fn atom(&mut self) -> Result<Regex, ParseError> {
+        let start = self.pos;
+
+        match self.next() {
+            Some('(') => {
+                let regex = try!(self.alternation());
+                match self.next() {
+                    Some(')') => Ok(regex),
+                    _ => self.error_at(start, format!("Unclosed `(`"))
+                }
+            },
+
+            Some('[') => self.class(start),
+            Some('.') => Ok(Class(self.alphabet.clone())),
+            Some('ε') => Ok(Epsilon),
+            Some('∅') => Ok(Empty),
+
+            Some('\\') => match self.next() {
+                Some(c) => self.literal(c, start),
+                None => self.error_at(start, format!("Pattern ends with `\\`"))
+            },
+
+            Some(c) if c == '*' || c == '+' || c == '?' => {
+                self.error_at(start, format!("`{}` does not follow an expression", c))
+            },
+
+            Some(']') => self.error_at(start, format!("Unmatched `]`")),
+            Some(c) => self.literal(c, start),
+
+            //concatenation never asks for an atom at the end of the pattern
+            None => unreachable!()
+        }
+    }
+
+    fn literal(&self, c: char, position: uint) -> Result<Regex, ParseError> {
+        if self.alphabet.contains(&c) {
+            Ok(Literal(c))
+        }
+
+        else {
+            self.error_at(position, format!("Symbol `{}` is not in the alphabet", c))
+        }
+    }
+
+    //Reads one symbol inside a class, handling escapes
+    fn class_symbol(&mut self, start: uint) -> Result<char, ParseError> {
+        match self.next() {
+            Some('\\') => match self.next() {
+                Some(c) => Ok(c),
+                None => self.error_at(start, format!("Unclosed `[`"))
+            },
+            Some(c) => Ok(c),
+            None => self.error_at(start, format!("Unclosed `[`"))
+        }
+    }
+
+    //class := '[' '^'? (symbol | symbol '-' symbol)+ ']'
+    //A range selects every symbol of the alphabet between its endpoints.
+    fn class(&mut self, start: uint) -> Result<Regex, ParseError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut members = vec!();
+        let mut items = 0u;
+
+        loop {
+            let pos = self.pos;
+
+            match self.peek() {
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                },
+                None => return self.error_at(start, format!("Unclosed `[`")),
+                _ => {}
+            }
+
+            let lo = try!(self.class_symbol(start));
+            items += 1;
+
+            //A '-' is only a range if something other than the closing bracket follows it
+            let is_range = self.peek() == Some('-') &&
+                           self.pos + 1 < self.chars.len() &&
+                           *self.chars.get(self.pos + 1) != ']';
+
+            if is_range {
+                self.pos += 1;
+                let hi = try!(self.class_symbol(start));
+
+                if lo > hi {
+                    return self.error_at(pos, format!("Invalid range `{}-{}`", lo, hi));
+                }
+
+                for sym in self.alphabet.iter() {
+                    if lo <= *sym && *sym <= hi && !members.contains(sym) {
+                        members.push(*sym);
+                    }
+                }
+            }
+
+            else {
+                if !self.alphabet.contains(&lo) {
+                    return self.error_at(pos, format!("Symbol `{}` is not in the alphabet", lo));
+                }
+
+                if !members.contains(&lo) {
+                    members.push(lo);
+                }
+            }
+        }
+
+        if items == 0 {
+            return self.error_at(start, format!("Empty character class"));
+        }
+
+        if negated {
+            let excluded = members;
+            members = self.alphabet.iter().filter(|x| !excluded.contains(*x)).map(|x| *x).collect();
+        }
+
+        members.sort();
+        Ok(Class(members))
+    }
+}
@@ -0,0 +1,667 @@
+use std::collections::bitv::BitvSet;
+use std::collections::hashmap::HashMap;
+use std::cmp;
+use std::cmp::{Less, Equal, Greater};
+use std::fmt;
+use std::mem;
+use std::sync::one::{Once, ONCE_INIT};
+use super::{DFA, Run, Word, Transition};
+use super::error::{AutomatonError, NoStates, StateOutOfRange, InvalidStart, InvalidAccept};
+use super::ranges::{next_char, prev_char, merge_ranges};
+
+//Builds a CharSet from the char property f the first time it is asked for, and keeps it
+//for the rest of the program, so testing every char is only paid for once
+macro_rules! cached_property(
+    ($f:expr) => ({
+        static mut TABLE: *const CharSet = 0 as *const CharSet;
+        static mut BUILT: Once = ONCE_INIT;
+        unsafe {
+            BUILT.doit(|| { TABLE = mem::transmute(box CharSet::from_fn($f)); });
+            (*TABLE).clone()
+        }
+    })
+)
+
+/// A predicate over chars, from an effective Boolean algebra.
+///
+/// Predicates are closed under conjunction, disjunction and negation, and it must be
+/// possible to decide whether a predicate holds for any char at all. Symbolic automata
+/// only combine and test predicates, so they never list the chars a predicate holds for.
+pub trait Predicate: Clone {
+    /// The predicate that holds for every char.
+    fn top() -> Self;
+
+    /// The predicate that holds for no char.
+    fn bottom() -> Self;
+
+    /// Returns a predicate holding where both self and other hold.
+    fn and(&self, other: &Self) -> Self;
+
+    /// Returns a predicate holding where self or other holds.
+    fn or(&self, other: &Self) -> Self;
+
+    /// Returns a predicate holding where self does not.
+    fn not(&self) -> Self;
+
+    /// Returns true if self holds for some char.
+    fn is_satisfiable(&self) -> bool;
+
+    /// Returns true if self holds for c.
+    fn contains(&self, c: char) -> bool;
+}
+
+/// A set of chars, stored as sorted, disjoint ranges.
+///
+/// Sets of chars are an effective Boolean algebra, so they can label the transitions of an `SFA`.
+/// Unicode categories are built with `from_fn`, which turns any test on chars into ranges.
+#[deriving(Clone, PartialEq, Eq, Hash)]
+pub struct CharSet {
+    ranges: Vec<(char, char)>
+}
+
+impl CharSet {
+    /// Returns the set of chars in any of ranges. Ranges include both ends.
+    pub fn new(ranges: &Vec<(char, char)>) -> CharSet {
+        let nonempty: Vec<(char, char)> = ranges.iter().filter(|&&(lo, hi)| lo <= hi).map(|&r| r).collect();
+        CharSet { ranges: merge_ranges(&nonempty) }
+    }
+
+    /// Returns the set of chars from lo to hi, including both.
+    pub fn range(lo: char, hi: char) -> CharSet {
+        CharSet::new(&vec!((lo, hi)))
+    }
+
+    /// Returns the set of chars for which f is true.
+    ///
+    /// Every char is tested, so this is much slower than the other operations.
+    pub fn from_fn(f: |char| -> bool) -> CharSet {
+        let mut ranges = vec!();
+        let mut open = None;
+        let mut c = '\0';
+        loop {
+            match (open, f(c)) {
+                (None, true) => open = Some(c),
+                (Some(lo), false) => {
+                    ranges.push((lo, prev_char(c).unwrap()));
+                    open = None;
+                },
+                _ => {}
+            }
+
+            match next_char(c) {
+                Some(n) => c = n,
+                None => break
+            }
+        }
+
+        match open {
+            Some(lo) => ranges.push((lo, c)),
+            None => {}
+        }
+
+        CharSet { ranges: ranges }
+    }
+
+    /// Returns the chars with the Unicode Alphabetic property.
+    ///
+    /// This and the other Unicode properties below are found with `from_fn` the first time
+    /// they are used, and copied from then on.
+    pub fn alphabetic() -> CharSet {
+        cached_property!(|c| c.is_alphabetic())
+    }
+
+    /// Returns the chars with the Unicode Lowercase property.
+    pub fn lowercase() -> CharSet {
+        cached_property!(|c| c.is_lowercase())
+    }
+
+    /// Returns the chars with the Unicode Uppercase property.
+    pub fn uppercase() -> CharSet {
+        cached_property!(|c| c.is_uppercase())
+    }
+
+    /// Returns the chars with the Unicode White_Space property.
+    pub fn whitespace() -> CharSet {
+        cached_property!(|c| c.is_whitespace())
+    }
+
+    /// Returns the decimal digits, in the Unicode category Nd.
+    pub fn digit() -> CharSet {
+        cached_property!(|c| c.is_digit())
+    }
+
+    /// Returns the control chars, in the Unicode category Cc.
+    pub fn control() -> CharSet {
+        cached_property!(|c| c.is_control())
+    }
+}
+
+impl Predicate for CharSet {
+    fn top() -> CharSet {
+        CharSet { ranges: vec!(('\0', '\U0010ffff')) }
+    }
+
+    fn bottom() -> CharSet {
+        CharSet { ranges: vec!() }
+    }
+
+    fn and(&self, other: &CharSet) -> CharSet {
+        let mut ranges = vec!();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (lo1, hi1) = *self.ranges.get(i);
+            let (lo2, hi2) = *other.ranges.get(j);
+            let (lo, hi) = (cmp::max(lo1, lo2), cmp::min(hi1, hi2));
+            if lo <= hi {
+                ranges.push((lo, hi));
+            }
+
+            //The range that ends first cannot overlap anything later in the other set
+            if hi1 < hi2 {
+                i += 1;
+            }
+
+            else {
+                j += 1;
+            }
+        }
+
+        CharSet { ranges: ranges }
+    }
+
+    fn or(&self, other: &CharSet) -> CharSet {
+        let mut ranges = self.ranges.clone();
+        ranges.push_all(other.ranges.as_slice());
+        CharSet { ranges: merge_ranges(&ranges) }
+    }
+
+    fn not(&self) -> CharSet {
+        let mut ranges = vec!();
+        //The first char that is not yet in a range or a gap
+        let mut next = Some('\0');
+        for &(lo, hi) in self.ranges.iter() {
+            match next {
+                Some(n) if n < lo => ranges.push((n, prev_char(lo).unwrap())),
+                _ => {}
+            }
+
+            next = next_char(hi);
+        }
+
+        match next {
+            Some(n) => ranges.push((n, '\U0010ffff')),
+            None => {}
+        }
+
+        CharSet { ranges: ranges }
+    }
+
+    fn is_satisfiable(&self) -> bool {
+        !self.ranges.is_empty()
+    }
+
+    fn contains(&self, c: char) -> bool {
+        self.ranges.as_slice().bsearch(|&(lo, hi)| {
+            if hi < c {
+                Less
+            }
+
+            else if lo > c {
+                Greater
+            }
+
+            else {
+                Equal
+            }
+        }).is_some()
+    }
+}
+
+impl fmt::Show for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "["));
+        for (i, &(lo, hi)) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                try!(write!(f, ", "));
+            }
+
+            if lo == hi {
+                try!(write!(f, "'{}'", lo));
+            }
+
+            else {
+                try!(write!(f, "'{}'-'{}'", lo, hi));
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Symbolic Finite Automaton
+///
+/// Like an NFA over chars, except that each transition is labelled by a predicate instead of
+/// a symbol, and can be taken on any char the predicate holds for. The alphabet is every char,
+/// so running an SFA never returns None.
+///
+/// Determinization, products and minimization split predicates into minterms: the satisfiable
+/// conjunctions of each predicate or its negation. Every transition treats all the chars of a
+/// minterm the same way, so minterms take the place of symbols, and the alphabet is never listed.
+#[deriving(Clone)]
+pub struct SFA<P = CharSet> {
+    start: uint,
+    //Transitions leaving each state, with the predicate a char must satisfy to take them
+    edges: Vec<Vec<(P, uint)>>,
+    accept: BitvSet
+}
+
+impl<P: Predicate> SFA<P> {
+    /// Creates a new SFA from transitions of the form (current state, predicate, next state).
+    ///
+    /// The predicates of transitions from the same state may overlap, making the SFA nondeterministic.
+    ///
+    /// Returns an Err if there are no states, if the start or an accept state does not exist,
+    /// or if a transition contains a state that does not exist.
+    pub fn new(
+        num_states: uint,
+        transitions: &Vec<Transition<P>>,
+        start: uint,
+        accept: &Vec<uint>
+    ) -> Result<SFA<P>, AutomatonError<P>> {
+
+        if num_states == 0 {
+            return Err(NoStates);
+        }
+
+        if start >= num_states {
+            return Err(InvalidStart(start));
+        }
+
+        let mut accept_states = BitvSet::new();
+        for &s in accept.iter() {
+            if s >= num_states {
+                return Err(InvalidAccept(s));
+            }
+
+            accept_states.insert(s);
+        }
+
+        let mut edges = Vec::from_fn(num_states, |_| vec!());
+        for t in transitions.iter() {
+            let &(curr, ref pred, next) = t;
+            for &state in [curr, next].iter() {
+                if state >= num_states {
+                    return Err(StateOutOfRange(t.clone(), state));
+                }
+            }
+
+            edges.get_mut(curr).push((pred.clone(), next));
+        }
+
+        Ok(SFA {
+            start: start,
+            edges: edges,
+            accept: accept_states
+        })
+    }
+
+    /// Returns the number of states.
+    pub fn num_states(&self) -> uint {
+        self.edges.len()
+    }
+
+    /// Returns true if no two transitions from the same state can be taken on the same char.
+    pub fn is_deterministic(&self) -> bool {
+        for out in self.edges.iter() {
+            for (i, &(ref p, _)) in out.iter().enumerate() {
+                for &(ref q, _) in out.slice_from(i + 1).iter() {
+                    if p.and(q).is_satisfiable() {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Returns an equivalent deterministic SFA using the subset construction.
+    ///
+    /// The transitions leaving a set of states are split into minterms, and each minterm leads
+    /// to the set of states reachable on the chars in it. Minterms leading to the same set are
+    /// joined into one transition. Chars on which no state of a set has a transition are left
+    /// without one, so the result may not be complete.
+    pub fn determinize(&self) -> SFA<P> {
+        let mut start = BitvSet::new();
+        start.insert(self.start);
+
+        //Each subset is keyed by its sorted list of states and numbered in the order found
+        let mut state_map: HashMap<Vec<uint>, uint> = HashMap::new();
+        state_map.insert(vec!(self.start), 0);
+
+        let mut subsets = vec!(start);
+        let mut edges = vec!();
+        let mut accept = BitvSet::new();
+        let mut curr = 0;
+
+        while curr < subsets.len() {
+            let set = subsets.get(curr).clone();
+            if set.iter().any(|s| self.accept.contains(&s)) {
+                accept.insert(curr);
+            }
+
+            let mut preds = vec!();
+            let mut targets = vec!();
+            for s in set.iter() {
+                for &(ref p, next) in self.edges.get(s).iter() {
+                    preds.push(p.clone());
+                    targets.push(next);
+                }
+            }
+
+            let mut out = vec!();
+            for &(ref term, ref inside) in minterms(&preds).iter() {
+                let mut next = BitvSet::new();
+                for i in inside.iter() {
+                    next.insert(*targets.get(i));
+                }
+
+                if next.is_empty() {
+                    continue;
+                }
+
+                let key: Vec<uint> = next.iter().collect();
+                let idx = match state_map.find_copy(&key) {
+                    Some(idx) => idx,
+                    None => {
+                        let idx = subsets.len();
+                        state_map.insert(key, idx);
+                        subsets.push(next);
+                        idx
+                    }
+                };
+
+                add_edge(&mut out, term, idx);
+            }
+
+            edges.push(out);
+            curr += 1;
+        }
+
+        SFA { start: 0, edges: edges, accept: accept }
+    }
+
+    /// Returns an equivalent SFA where every state has a transition on every char.
+    ///
+    /// Chars that a state has no transition on lead to a new rejecting sink state,
+    /// which is only added if it is needed.
+    pub fn complete(&self) -> SFA<P> {
+        let sink = self.edges.len();
+        let mut sfa = self.clone();
+        let mut needs_sink = false;
+
+        for out in sfa.edges.mut_iter() {
+            let none: P = Predicate::bottom();
+            let rest = out.iter().fold(none, |acc, &(ref p, _)| acc.or(p)).not();
+            if rest.is_satisfiable() {
+                out.push((rest, sink));
+                needs_sink = true;
+            }
+        }
+
+        if needs_sink {
+            sfa.edges.push(vec!((Predicate::top(), sink)));
+        }
+
+        sfa
+    }
+
+    /// Returns an SFA accepting the strings accepted by self or other.
+    pub fn union(&self, other: &SFA<P>) -> SFA<P> {
+        self.product(other, |x, y| { x || y })
+    }
+
+    /// Returns an SFA accepting the strings accepted by both self and other.
+    pub fn intersect(&self, other: &SFA<P>) -> SFA<P> {
+        self.product(other, |x, y| { x && y })
+    }
+
+    /// Returns an SFA accepting the strings accepted by self but not by other.
+    pub fn difference(&self, other: &SFA<P>) -> SFA<P> {
+        self.product(other, |x, y| { x && !y })
+    }
+
+    /// Returns an SFA accepting the strings accepted by exactly one of self and other.
+    pub fn symmetric_difference(&self, other: &SFA<P>) -> SFA<P> {
+        self.product(other, |x, y| { x != y })
+    }
+
+    //Takes the product of the deterministic, complete forms of self and other, where a pair of
+    //states accepts if f(self accepts, other accepts). Only pairs reachable from the start are built.
+    fn product(&self, other: &SFA<P>, f: |bool, bool| -> bool) -> SFA<P> {
+        let a = self.determinize().complete();
+        let b = other.determinize().complete();
+
+        let mut state_map = HashMap::new();
+        state_map.insert((a.start, b.start), 0u);
+
+        let mut pairs = vec!((a.start, b.start));
+        let mut edges = vec!();
+        let mut accept = BitvSet::new();
+        let mut curr = 0;
+
+        while curr < pairs.len() {
+            let (x, y) = *pairs.get(curr);
+            if f(a.accept.contains(&x), b.accept.contains(&y)) {
+                accept.insert(curr);
+            }
+
+            //A char leads to a pair of states if it satisfies both predicates
+            let mut out = vec!();
+            for &(ref p, i) in a.edges.get(x).iter() {
+                for &(ref q, j) in b.edges.get(y).iter() {
+                    let both = p.and(q);
+                    if !both.is_satisfiable() {
+                        continue;
+                    }
+
+                    let idx = match state_map.find_copy(&(i, j)) {
+                        Some(idx) => idx,
+                        None => {
+                            let idx = pairs.len();
+                            state_map.insert((i, j), idx);
+                            pairs.push((i, j));
+                            idx
+                        }
+                    };
+
+                    out.push((both, idx));
+                }
+            }
+
+            edges.push(out);
+            curr += 1;
+        }
+
+        SFA { start: 0, edges: edges, accept: accept }
+    }
+
+    /// Returns an SFA accepting every string that self rejects.
+    pub fn complement(&self) -> SFA<P> {
+        let mut sfa = self.determinize().complete();
+        let mut accept = BitvSet::new();
+        for s in range(0, sfa.edges.len()) {
+            if !sfa.accept.contains(&s) {
+                accept.insert(s);
+            }
+        }
+
+        sfa.accept = accept;
+        sfa
+    }
+
+    /// Reduces the amount of states in-place to the minimum necessary to recognize the same language.
+    ///
+    /// self is made deterministic and complete, and all of its predicates are split into minterms.
+    /// With the minterms as symbols it is an ordinary DFA, which is minimized with `DFA::minimize`.
+    /// The minterms leading from each state to the same next state are then joined into one predicate.
+    pub fn minimize(&mut self) {
+        let sfa = self.determinize().complete();
+
+        let mut preds = vec!();
+        let mut ends = vec!();
+        for (s, out) in sfa.edges.iter().enumerate() {
+            for &(ref p, next) in out.iter() {
+                preds.push(p.clone());
+                ends.push((s, next));
+            }
+        }
+
+        let terms = minterms(&preds);
+        let mut transitions = vec!();
+        for (k, &(_, ref inside)) in terms.iter().enumerate() {
+            for i in inside.iter() {
+                let (curr, next) = *ends.get(i);
+                transitions.push((curr, k, next));
+            }
+        }
+
+        //The transitions from each state are disjoint and cover every char,
+        //so each minterm is inside exactly one of them
+        let symbols: Vec<uint> = range(0, terms.len()).collect();
+        let accept: Vec<uint> = sfa.accept.iter().collect();
+        let mut dfa = DFA::new(sfa.edges.len(), &symbols, &transitions, sfa.start, &accept).ok().unwrap();
+        dfa.minimize();
+
+        let mut edges = vec!();
+        let mut accept = BitvSet::new();
+        for s in range(0, dfa.num_states()) {
+            let mut out = vec!();
+            for (k, &(ref term, _)) in terms.iter().enumerate() {
+                add_edge(&mut out, term, dfa.next_state(s, &k).unwrap());
+            }
+
+            edges.push(out);
+            if dfa.is_accept_state(s) {
+                accept.insert(s);
+            }
+        }
+
+        *self = SFA { start: dfa.start_state(), edges: edges, accept: accept };
+    }
+
+    /// Return true if no accept state can be reached, so self accepts no strings.
+    pub fn is_empty(&self) -> bool {
+        let mut reachable = BitvSet::new();
+        reachable.insert(self.start);
+        let mut stack = vec!(self.start);
+
+        loop {
+            let s = match stack.pop() {
+                Some(s) => s,
+                None => return true
+            };
+
+            if self.accept.contains(&s) {
+                return false;
+            }
+
+            for &(ref p, next) in self.edges.get(s).iter() {
+                if p.is_satisfiable() && reachable.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    /// Checks if self and other accept the same strings.
+    pub fn equivalent(&self, other: &SFA<P>) -> bool {
+        self.symmetric_difference(other).is_empty()
+    }
+}
+
+//Splits every char into minterms of preds: satisfiable predicates that are, for each of preds,
+//either inside of it or disjoint from it. Each comes with the indices of the preds it is inside of.
+fn minterms<P: Predicate>(preds: &Vec<P>) -> Vec<(P, BitvSet)> {
+    let all: P = Predicate::top();
+    let mut terms = vec!((all, BitvSet::new()));
+
+    for (i, p) in preds.iter().enumerate() {
+        let not_p = p.not();
+        let mut split = vec!();
+        for (term, inside) in terms.move_iter() {
+            let with = term.and(p);
+            let without = term.and(&not_p);
+            if with.is_satisfiable() {
+                let mut inside_p = inside.clone();
+                inside_p.insert(i);
+                split.push((with, inside_p));
+            }
+
+            if without.is_satisfiable() {
+                split.push((without, inside));
+            }
+        }
+
+        terms = split;
+    }
+
+    terms
+}
+
+//Adds a transition on pred to next, joining it to the existing transition to next if there is one
+fn add_edge<P: Predicate>(edges: &mut Vec<(P, uint)>, pred: &P, next: uint) {
+    match edges.iter().position(|&(_, n)| n == next) {
+        Some(i) => {
+            let joined = {
+                let &(ref p, _) = edges.get(i);
+                p.or(pred)
+            };
+
+            *edges.get_mut(i) = (joined, next);
+        },
+        None => edges.push((pred.clone(), next))
+    }
+}
+
+impl<P: Predicate> Run for SFA<P> {
+    fn run<I: Word<char>>(&self, input: I) -> Option<bool> {
+        let mut states = BitvSet::new();
+        states.insert(self.start);
+
+        for c in input.symbols().move_iter() {
+            let mut next = BitvSet::new();
+            for s in states.iter() {
+                for &(ref p, n) in self.edges.get(s).iter() {
+                    if p.contains(c) {
+                        next.insert(n);
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                return Some(false);
+            }
+
+            states = next;
+        }
+
+        Some(states.iter().any(|s| self.accept.contains(&s)))
+    }
+}
+
+impl<P: Predicate + fmt::Show> fmt::Show for SFA<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "Start State: {}\n", self.start));
+        try!(write!(f, "Accept States: {}\n", self.accept));
+        try!(write!(f, "Transitions:\n"));
+
+        for (curr, out) in self.edges.iter().enumerate() {
+            for &(ref p, next) in out.iter() {
+                try!(write!(f, "  ({}, {}) -> {}\n", curr, p, next));
+            }
+        }
+
+        Ok(())
+    }
+}
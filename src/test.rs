@@ -1,6 +1,21 @@
+#![feature(phase)]
+
+#[phase(plugin, link)]
 extern crate hephaestus;
+extern crate num;
+extern crate test;
 
-use hephaestus::{DFA, Run, NFA};
+use hephaestus::{DFA, Run, NFA, DfaBuilder, NfaBuilder, RangeDFA, Symbol, Word};
+use hephaestus::{SFA, CharSet, Predicate};
+use hephaestus::regex;
+use hephaestus::error::{TransitionCount, DuplicateTransition, StateOutOfRange, ReservedSymbol, TooManyStates};
+use hephaestus::error::{UnknownSymbol, MissingTransition, InvalidStart, InvalidAccept, EpsilonOutOfRange};
+use hephaestus::error::{UnreachableState, DeadState, UnknownState, NoStart, InvalidRange};
+use std::num::FromPrimitive;
+use std::rand::{Rng, StdRng, SeedableRng};
+use std::collections::hashmap::HashMap;
+use num::bigint::{BigUint, BigInt};
+use test::Bencher;
 
 ///////////////////////////
 ////  DFA Unit Tests  /////
@@ -18,20 +33,66 @@ fn dfa_validates_transitions() {
 
     match DFA::new(states, &alphabet, &t1, start, &accept) {
         Ok(_) => fail!(),
-        Err(e) => assert_eq!(e, format!("Incorrect number of transitions"))
+        Err(e) => {
+            assert_eq!(e, TransitionCount(1, 2));
+            assert_eq!(format!("{}", e).as_slice(), "Incorrect number of transitions");
+        }
     }
 
     match DFA::new(states, &alphabet, &t2, start, &accept) {
         Ok(_) => fail!(),
-        Err(e) => assert_eq!(e, format!("Duplicate transition: (0, '0') -> 0"))
+        Err(e) => {
+            assert_eq!(e, DuplicateTransition((0, '0', 0)));
+            assert_eq!(format!("{}", e).as_slice(), "Duplicate transition: (0, '0') -> 0");
+        }
     }
 
     match DFA::new(states, &alphabet, &t3, start, &accept) {
         Ok(_) => fail!(),
-        Err(e) => assert_eq!(e, format!("In transition: (0, '1') -> 5: State `5` does not exist"))
+        Err(e) => {
+            assert_eq!(e, StateOutOfRange((0, '1', 5), 5));
+            assert_eq!(format!("{}", e).as_slice(), "In transition: (0, '1') -> 5: State `5` does not exist");
+        }
+    }
+
+    //States are numbered with 32 bits
+    match DFA::new_partial(0xFFFFFFFFu, &alphabet, &vec!(), start, &accept) {
+        Ok(_) => fail!(),
+        Err(e) => assert_eq!(e, TooManyStates(0xFFFFFFFF))
     }
 }
 
+#[test]
+fn dfa_validation_reports_every_problem() {
+    let alphabet = vec!('0', '1');
+    let t = vec!((0, '0', 1), (0, '0', 2), (0, '2', 0), (1, '0', 7));
+    let report = DFA::validate(3, &alphabet, &t, 0, &vec!(1, 9));
+
+    assert!(!report.is_valid());
+    assert_eq!(report.errors, vec!(UnknownSymbol((0, '2', 0)),
+                                   StateOutOfRange((1, '0', 7), 7),
+                                   DuplicateTransition((0, '0', 2)),
+                                   MissingTransition(0, '1'),
+                                   MissingTransition(1, '0'),
+                                   MissingTransition(1, '1'),
+                                   MissingTransition(2, '0'),
+                                   MissingTransition(2, '1'),
+                                   InvalidAccept(9)));
+    assert_eq!(report.warnings, vec!(UnreachableState(2)));
+
+    match DFA::new(1, &alphabet, &vec!((0, '0', 0), (0, '1', 0)), 0, &vec!(3)) {
+        Ok(_) => fail!(),
+        Err(e) => assert_eq!(e, InvalidAccept(3))
+    }
+
+    //A valid DFA with a sink state only gets a warning
+    let t = vec!((0, '0', 1), (0, '1', 0), (1, '0', 1), (1, '1', 1));
+    let report = DFA::validate(2, &alphabet, &t, 0, &vec!(0));
+    assert!(report.is_valid());
+    assert_eq!(report.warnings, vec!(DeadState(1)));
+    assert_eq!(format!("{}", report).as_slice(), "Warning: State `1` cannot reach an accept state\n");
+}
+
 #[test]
 fn dfa_accepts_correct_inputs() {
     let alphabet = vec!('0', '1');
@@ -208,6 +269,134 @@ fn dfa_minimization() {
     assert_eq!(format!("{}", dfa).as_slice(), expected);
 }
 
+#[test]
+fn dfa_minimization_merges_equivalent_states() {
+    //Counts a's mod 6 and accepts multiples of 3, which only needs a count mod 3.
+    //State 6 is unreachable, and the start is not state 0.
+    let a = vec!('a', 'b');
+    let mut t = vec!((6, 'a', 0), (6, 'b', 6));
+    for i in range(0, 6u) {
+        t.push((i, 'a', (i + 1) % 6));
+        t.push((i, 'b', i));
+    }
+
+    let original = DFA::new(7, &a, &t, 3, &vec!(0, 3)).unwrap();
+    let mut dfa = original.clone();
+    dfa.minimize();
+
+    assert_eq!(dfa.num_states(), 3);
+    assert!(dfa == original);
+    assert_eq!(dfa.run("abaab").unwrap(), true);
+    assert_eq!(dfa.run("aab").unwrap(), false);
+
+    //A partial DFA's dead state is kept as a state of the minimal DFA
+    let only_a = DFA::new_partial(2, &a, &vec!((0, 'a', 1), (1, 'a', 0)), 0, &vec!(0, 1)).unwrap();
+    let mut minimal = only_a.clone();
+    minimal.minimize();
+    assert_eq!(minimal.num_states(), 2);
+    assert!(minimal.is_complete());
+    assert!(minimal == only_a);
+}
+
+#[test]
+fn dfa_minimization_handles_long_chains() {
+    //Accepts only a^(n-1). Every state of the chain is at a different distance from the
+    //accept state, so the search for reachable states has to go n levels deep.
+    let n = 200000u;
+    let t: Vec<(uint, char, uint)> = range(0, n - 1).map(|i| (i, 'a', i + 1)).collect();
+    let mut dfa = DFA::new_partial(n, &vec!('a', 'b'), &t, 0, &vec!(n - 1)).unwrap();
+    dfa.minimize();
+
+    //The chain and the dead state
+    assert_eq!(dfa.num_states(), n + 1);
+    assert_eq!(dfa.run(String::from_char(n - 1, 'a').as_slice()).unwrap(), true);
+    assert_eq!(dfa.run(String::from_char(n, 'a').as_slice()).unwrap(), false);
+}
+
+//Checks that a and b are the same DFA up to the numbering of their states,
+//pairing up states by following both DFAs from their start states
+fn isomorphic(a: &DFA, b: &DFA, alphabet: &Vec<char>) -> bool {
+    if a.num_states() != b.num_states() {
+        return false;
+    }
+
+    let mut pair = Vec::from_elem(a.num_states(), None);
+    let mut paired = Vec::from_elem(b.num_states(), false);
+    *pair.get_mut(a.start_state()) = Some(b.start_state());
+    *paired.get_mut(b.start_state()) = true;
+    let mut stack = vec!((a.start_state(), b.start_state()));
+
+    loop {
+        let (p, q) = match stack.pop() {
+            Some(states) => states,
+            None => return true
+        };
+
+        if a.is_accept_state(p) != b.is_accept_state(q) {
+            return false;
+        }
+
+        for c in alphabet.iter() {
+            match (a.next_state(p, c), b.next_state(q, c)) {
+                (Some(x), Some(y)) => match *pair.get(x) {
+                    Some(z) if z != y => return false,
+                    Some(_) => {},
+                    None => {
+                        if *paired.get(y) {
+                            return false;
+                        }
+
+                        *pair.get_mut(x) = Some(y);
+                        *paired.get_mut(y) = true;
+                        stack.push((x, y));
+                    }
+                },
+                (None, None) => {},
+                _ => return false
+            }
+        }
+    }
+}
+
+#[test]
+fn dfa_minimization_algorithms_agree() {
+    let alphabet = vec!('a', 'b', 'c');
+    let mut rng: StdRng = SeedableRng::from_seed(&[2u, 5, 0]);
+
+    for _ in range(0, 300u) {
+        let n = rng.gen_range(1u, 9);
+        let mut transitions = vec!();
+        for s in range(0, n) {
+            for &c in alphabet.iter() {
+                //Leave some transitions out so partial DFAs are covered too
+                if !rng.gen_weighted_bool(8) {
+                    transitions.push((s, c, rng.gen_range(0, n)));
+                }
+            }
+        }
+
+        let accept: Vec<uint> = range(0, n).filter(|_| rng.gen()).collect();
+        let start = rng.gen_range(0, n);
+        let dfa = DFA::new_partial(n, &alphabet, &transitions, start, &accept).unwrap();
+
+        let mut hopcroft = dfa.clone();
+        let mut moore = dfa.clone();
+        let mut brzozowski = dfa.clone();
+        hopcroft.minimize();
+        moore.minimize_moore();
+        brzozowski.minimize_brzozowski();
+
+        assert!(hopcroft == dfa);
+        assert_eq!(format!("{}", moore), format!("{}", hopcroft));
+        assert!(isomorphic(&hopcroft, &brzozowski, &alphabet));
+
+        //A minimal DFA cannot be made any smaller
+        let mut again = brzozowski.clone();
+        again.minimize();
+        assert!(isomorphic(&again, &brzozowski, &alphabet));
+    }
+}
+
 #[test]
 fn dfa_complement_intersection_is_empty() {
 	let n = 2;
@@ -243,6 +432,492 @@ fn dfa_equality() {
 }
 
 
+#[test]
+fn dfa_equivalence_ignores_structure() {
+    let a = vec!('a', 'b');
+    //Accepts strings with an odd number of a's, with a redundant state
+    let odd = DFA::new(2, &a, &vec!((0, 'a', 1), (0, 'b', 0),
+                                     (1, 'a', 0), (1, 'b', 1)), 0, &vec!(1)).unwrap();
+    let odd_redundant = DFA::new(3, &a, &vec!((0, 'a', 1), (0, 'b', 0),
+                                               (1, 'a', 2), (1, 'b', 1),
+                                               (2, 'a', 1), (2, 'b', 0)), 0, &vec!(1)).unwrap();
+    let even = odd.complement();
+
+    assert!(odd.equivalent(&odd_redundant));
+    assert_eq!(odd, odd_redundant);
+    assert!(!odd.equivalent(&even));
+    assert!(odd != even);
+
+    let other_alphabet = DFA::new(1, &vec!('a'), &vec!((0, 'a', 0)), 0, &vec!()).unwrap();
+    assert!(!odd.equivalent(&other_alphabet));
+}
+
+#[test]
+fn dfa_products_align_alphabets() {
+    //Strings of only a's, over {a} and {a, b}
+    let a_star = DFA::new(1, &vec!('a'), &vec!((0, 'a', 0)), 0, &vec!(0)).unwrap();
+    let also_a_star = DFA::new(2, &vec!('a', 'b'), &vec!((0, 'a', 0), (0, 'b', 1),
+                                                          (1, 'a', 1), (1, 'b', 1)), 0, &vec!(0)).unwrap();
+    //Strings of only b's, over {b, c}
+    let b_star = DFA::new(1, &vec!('b', 'c'), &vec!((0, 'b', 0), (0, 'c', 1),
+                                                     (1, 'b', 1), (1, 'c', 1)), 0, &vec!(0)).unwrap();
+
+    assert!(a_star.union(&b_star).is_none());
+    assert_eq!(a_star, also_a_star);
+    assert!(!a_star.equivalent_strict(&also_a_star));
+
+    let union = a_star.union_aligned(&b_star);
+    for s in vec!("", "aaa", "bb").iter() {
+        assert_eq!(union.run(*s).unwrap(), true);
+    }
+
+    for s in vec!("ab", "c", "bc").iter() {
+        assert_eq!(union.run(*s).unwrap(), false);
+    }
+
+    let ints = a_star.intersect_aligned(&b_star);
+    assert_eq!(ints.run("").unwrap(), true);
+    assert_eq!(ints.run("a").unwrap(), false);
+    assert_eq!(ints.run("c").unwrap(), false);
+
+    let extended = a_star.extend_alphabet(&vec!('b', 'a'));
+    assert_eq!(extended.run("b").unwrap(), false);
+    assert!(extended.equivalent_strict(&also_a_star));
+}
+
+#[test]
+fn dfa_boolean_operations() {
+    let alphabet = vec!('a', 'b');
+    let starts_a = regex::compile("a(a|b)*", &alphabet).unwrap().to_dfa();
+    let ends_b = regex::compile("(a|b)*b", &alphabet).unwrap().to_dfa();
+
+    //Operators borrow their operands
+    let diff = starts_a - ends_b;
+    let sym_diff = starts_a ^ ends_b;
+    let both = starts_a & ends_b;
+    let either = starts_a | ends_b;
+
+    let cases = vec!(("", false, false), ("a", true, false), ("b", false, true),
+                     ("ab", true, true), ("ba", false, false), ("abb", true, true));
+
+    for &(s, x, y) in cases.iter() {
+        assert_eq!(diff.run(s).unwrap(), x && !y);
+        assert_eq!(sym_diff.run(s).unwrap(), x != y);
+        assert_eq!(both.run(s).unwrap(), x && y);
+        assert_eq!(either.run(s).unwrap(), x || y);
+        assert_eq!(starts_a.xnor(&ends_b).run(s).unwrap(), x == y);
+        assert_eq!(starts_a.product_with(&ends_b, |p, q| { !p || q }).run(s).unwrap(), !x || y);
+    }
+
+    assert_eq!(diff, starts_a & !ends_b);
+    assert_eq!(sym_diff, !starts_a.xnor(&ends_b));
+    assert_eq!(either, !(!starts_a & !ends_b));
+}
+
+#[test]
+fn dfa_enumerates_in_shortlex_order() {
+    let alphabet = vec!('b', 'a');
+    //Accepts strings with an odd number of a's
+    let odd = DFA::new(2, &alphabet, &vec!((0, 'a', 1), (0, 'b', 0),
+                                            (1, 'a', 0), (1, 'b', 1)), 0, &vec!(1)).unwrap();
+
+    let expected = vec!("a", "ab", "ba", "aaa", "abb", "bab", "bba");
+    let first: Vec<String> = expected.iter().map(|s| s.to_string()).collect();
+    assert_eq!(odd.first_accepted(7), first);
+
+    let short: Vec<String> = odd.accepted_strings_up_to(2).collect();
+    assert_eq!(short, vec!("a".to_string(), "ab".to_string(), "ba".to_string()));
+
+    //Finite languages end the iterator
+    let finite = regex::compile("a|ab|b?", &vec!('a', 'b')).unwrap().to_dfa();
+    let all: Vec<String> = finite.accepted_strings().collect();
+    assert_eq!(all, vec!("".to_string(), "a".to_string(), "b".to_string(), "ab".to_string()));
+
+    let none = odd.intersect(&odd.complement()).unwrap();
+    assert_eq!(none.accepted_strings().next(), None);
+}
+
+#[test]
+fn dfa_counts_accepted_strings() {
+    let alphabet = vec!('a', 'b');
+    //Strings with no two consecutive b's are counted by the Fibonacci numbers
+    let no_bb = regex::compile("(a|ba)*b?", &alphabet).unwrap().to_dfa();
+    let big = |n: uint| -> BigUint { FromPrimitive::from_uint(n).unwrap() };
+
+    let fib = vec!(1u, 2, 3, 5, 8, 13, 21, 34);
+    for (n, &count) in fib.iter().enumerate() {
+        assert_eq!(no_bb.count_accepted(n), big(count));
+    }
+
+    assert_eq!(no_bb.count_accepted_up_to(3), big(1 + 2 + 3 + 5));
+
+    //2^100 strings of length 100, more than fit in a machine word
+    let all = regex::compile("(a|b)*", &alphabet).unwrap().to_dfa();
+    let expected: BigUint = FromPrimitive::from_u64(1u64 << 50).unwrap();
+    assert_eq!(all.count_accepted(100), expected.mul(&expected));
+}
+
+#[test]
+fn dfa_generating_function() {
+    let alphabet = vec!('a', 'b');
+    let big = |n: int| -> BigInt { FromPrimitive::from_int(n).unwrap() };
+
+    //Strings of even length: 1 / (1 - 4x^2)
+    //Minimizing first keeps the fraction in lowest terms
+    let mut even = regex::compile("((a|b)(a|b))*", &alphabet).unwrap().to_dfa();
+    even.minimize();
+    let gf = even.generating_function();
+    assert_eq!(gf.numerator, vec!(big(1)));
+    assert_eq!(gf.denominator, vec!(big(1), big(0), big(-4)));
+    assert_eq!(format!("{}", gf).as_slice(), "(1) / (1 - 4x^2)");
+
+    //No two consecutive b's: (1 + x) / (1 - x - x^2)
+    let mut no_bb = regex::compile("(a|ba)*b?", &alphabet).unwrap().to_dfa();
+    no_bb.minimize();
+    let gf = no_bb.generating_function();
+    assert_eq!(gf.numerator, vec!(big(1), big(1)));
+    assert_eq!(gf.denominator, vec!(big(1), big(-1), big(-1)));
+
+    let none = even.intersect(&even.complement()).unwrap();
+    assert_eq!(format!("{}", none.generating_function()).as_slice(), "(0) / (1)");
+}
+
+#[test]
+fn dfa_samples_accepted_strings() {
+    let alphabet = vec!('a', 'b');
+    let mut rng: StdRng = SeedableRng::from_seed(&[1u, 8, 1]);
+    let dfa = regex::compile("ab|ba|a(a|b)*b", &alphabet).unwrap().to_dfa();
+
+    //Of length 2 there are only ab and ba, as the only string of a(a|b)*b is ab again,
+    //so each should be drawn about half the time
+    let mut counts = HashMap::new();
+    for _ in range(0, 2000u) {
+        let s = dfa.sample(2, &mut rng).unwrap();
+        assert_eq!(dfa.run(s.as_slice()).unwrap(), true);
+        *counts.find_or_insert(s, 0u) += 1;
+    }
+
+    assert_eq!(counts.len(), 2);
+    for &n in counts.values() {
+        assert!(n > 900 && n < 1100, "drew a string {} times out of 2000", n);
+    }
+
+    //Of length 4 there are aaab, aabb, abab and abbb
+    let sampler = dfa.sampler(6);
+    let mut counts = HashMap::new();
+    for _ in range(0, 4000u) {
+        let s: String = sampler.sample(4, &mut rng).unwrap().move_iter().collect();
+        *counts.find_or_insert(s, 0u) += 1;
+    }
+
+    assert_eq!(counts.len(), 4);
+    for &n in counts.values() {
+        assert!(n > 850 && n < 1150, "drew a string {} times out of 4000", n);
+    }
+
+    for _ in range(0, 50u) {
+        let s = sampler.sample(6, &mut rng).unwrap();
+        assert_eq!(s.len(), 6);
+        assert_eq!(dfa.run(s.as_slice()).unwrap(), true);
+
+        let t = sampler.sample_lengths(&vec!(5.0, 1.0, 0.0, 2.0), &mut rng).unwrap();
+        assert_eq!(t.len(), 3);
+        assert_eq!(dfa.run(t.as_slice()).unwrap(), true);
+
+        let u = sampler.sample_up_to(&mut rng).unwrap();
+        assert!(u.len() <= 6);
+        assert_eq!(dfa.run(u.as_slice()).unwrap(), true);
+    }
+
+    assert_eq!(sampler.sample(1, &mut rng), None);
+    assert_eq!(sampler.sample(7, &mut rng), None);
+}
+
+#[test]
+fn dfa_language_queries() {
+    let alphabet = vec!('a', 'b');
+    let finite = regex::compile("ab?|ba(a|b)", &alphabet).unwrap().to_dfa();
+    let infinite = regex::compile("a(ba)*", &alphabet).unwrap().to_dfa();
+    let all = regex::compile("(a|b)*", &alphabet).unwrap().to_dfa();
+    let none = regex::compile("a∅", &alphabet).unwrap().to_dfa();
+
+    assert!(finite.is_finite() && !infinite.is_finite() && !all.is_finite() && none.is_finite());
+    assert!(none.is_empty() && !finite.is_empty() && !all.is_empty());
+    assert!(all.is_universal() && !infinite.is_universal() && !none.is_universal());
+
+    assert_eq!(finite.longest_accepted_length(), Some(3));
+    assert_eq!(infinite.longest_accepted_length(), None);
+    assert_eq!(none.longest_accepted_length(), None);
+}
+
+#[test]
+fn dfa_partial_transitions() {
+    let a = vec!('a', 'b');
+
+    //Strings of only a's, with no transitions on b
+    let only_a = DFA::new_partial(1, &a, &vec!((0, 'a', 0)), 0, &vec!(0)).unwrap();
+    assert!(!only_a.is_complete());
+    assert_eq!(only_a.run("aaa"), Some(true));
+    assert_eq!(only_a.run("aba"), Some(false));
+    assert_eq!(only_a.run("bc"), None);
+
+    match DFA::new_partial(1, &a, &vec!((0, 'a', 0), (0, 'a', 0)), 0, &vec!(0)) {
+        Ok(_) => fail!("Duplicate transitions should be rejected"),
+        Err(e) => assert_eq!(e, DuplicateTransition((0, 'a', 0)))
+    }
+
+    //The complement accepts every string containing a b
+    let not_only_a = only_a.complement();
+    assert_eq!(not_only_a.run("ab"), Some(true));
+    assert_eq!(not_only_a.run("aa"), Some(false));
+    assert!(not_only_a.is_complete());
+
+    let complete = only_a.complete();
+    assert!(complete.is_complete());
+    assert!(complete == only_a);
+    assert_eq!(complete.run("aba"), Some(false));
+
+    let trimmed = complete.trim();
+    assert!(!trimmed.is_complete());
+    assert!(trimmed == only_a);
+    assert_eq!(format!("{}", trimmed), format!("{}", only_a));
+
+    assert_eq!(only_a.first_accepted(3), vec!("".to_string(), "a".to_string(), "aa".to_string()));
+    let one: BigUint = FromPrimitive::from_uint(1).unwrap();
+    assert_eq!(only_a.count_accepted(4), one);
+
+    let mut minimal = only_a.clone();
+    minimal.minimize();
+    assert!(minimal == only_a);
+}
+
+#[test]
+fn dfa_builder_names_states() {
+    let mut builder = DfaBuilder::new(&vec!('a', 'b'));
+    builder.start_state("even").accept_state("even").state("odd")
+           .transition("even", 'a', "odd").transition("odd", 'a', "even")
+           .transition("even", 'b', "even").transition("odd", 'b', "odd");
+
+    let dfa = builder.build().unwrap();
+    assert_eq!(dfa.run("abab"), Some(true));
+    assert_eq!(dfa.run("ab"), Some(false));
+    assert_eq!(dfa.find_state("odd"), Some(1));
+    assert_eq!(dfa.trace("aab"), Some(vec!("even".to_string(), "odd".to_string(),
+                                           "even".to_string(), "even".to_string())));
+    assert_eq!(dfa.trace("c"), None);
+
+    let expected = "Alphabet: [a, b]\nStart State: even\nAccept States: [even]\nTransitions:\n  \
+                    (even, 'a') -> odd\n  (even, 'b') -> even\n  (odd, 'a') -> even\n  (odd, 'b') -> odd\n";
+    assert_eq!(format!("{}", dfa).as_slice(), expected);
+
+    //Missing transitions need the partial option
+    let mut builder = DfaBuilder::new(&vec!('a', 'b'));
+    builder.start_state("q0").accept_state("q1").transition("q0", 'a', "q1");
+    assert!(builder.build().is_err());
+    let partial = builder.partial().build().unwrap();
+    assert_eq!(partial.trace("ab"), Some(vec!("q0".to_string(), "q1".to_string())));
+
+    builder.transition("q1", 'b', "q2");
+    assert_eq!(builder.build().err(), Some(UnknownState("q2".to_string())));
+    assert_eq!(DfaBuilder::new(&vec!('a')).state("q0").build().err(), Some(NoStart));
+}
+
+#[test]
+fn dfa_macro_builds_named_dfa() {
+    //Strings over 0 and 1 with an odd number of 1s
+    let dfa = dfa!{
+        start even;
+        accept odd;
+        even -'0'-> even;
+        even -'1'-> odd;
+        odd -'0'-> odd;
+        odd -'1'-> even;
+    }.unwrap();
+
+    assert_eq!(dfa.run("0100"), Some(true));
+    assert_eq!(dfa.run("11"), Some(false));
+    assert_eq!(dfa.find_state("odd"), Some(1));
+
+    //Symbols may be grouped on one arrow, and missing transitions are caught when built
+    let all = dfa!{ start q; accept q; q -a,b-> q; }.unwrap();
+    assert!(all.is_universal());
+    let bits = dfa!{ start q; accept q; q -0,1-> q; }.unwrap();
+    assert_eq!(bits.run("0110"), Some(true));
+    assert!(dfa!{ start q0; accept q1; q0 -a-> q1; q0 -b-> q0; }.is_err());
+}
+
+#[test]
+fn dfa_over_bytes() {
+    //Accepts byte strings with an even number of b's
+    let alphabet = vec!(b'a', b'b');
+    let transitions = vec!((0, b'a', 0), (0, b'b', 1),
+                           (1, b'a', 1), (1, b'b', 0));
+    let dfa = DFA::new(2, &alphabet, &transitions, 0, &vec!(0)).unwrap();
+
+    assert_eq!(dfa.run(b"").unwrap(), true);
+    assert_eq!(dfa.run(b"abab").unwrap(), true);
+    assert_eq!(dfa.run(b"ab").unwrap(), false);
+    assert_eq!(dfa.run(b"abc"), None);
+
+    //The same language with a redundant state
+    let redundant = vec!((0, b'a', 0), (0, b'b', 1),
+                         (1, b'a', 1), (1, b'b', 2),
+                         (2, b'a', 2), (2, b'b', 1));
+    let mut big = DFA::new(3, &alphabet, &redundant, 0, &vec!(0, 2)).unwrap();
+    assert!(big == dfa);
+    assert!((big.complement() & dfa).is_empty());
+
+    big.minimize();
+    assert_eq!(big.count_accepted(4), dfa.count_accepted(4));
+
+    //Strings come back as byte vectors
+    let words: Vec<Vec<u8>> = dfa.accepted_words_up_to(2).collect();
+    assert_eq!(words, vec!(vec!(), vec!(b'a'), vec!(b'a', b'a'), vec!(b'b', b'b')));
+    assert_eq!(dfa.first_accepted_words(2), vec!(vec!(), vec!(b'a')));
+
+    let only_a = DFA::new(1, &vec!(b'a'), &vec!((0, b'a', 0)), 0, &vec!(0)).unwrap();
+    assert_eq!(only_a.subset_witness(&dfa), None);
+    assert_eq!(dfa.subset_witness(&only_a), Some(vec!(b'b', b'b')));
+    assert_eq!(dfa.distinguishing_word(&only_a), Some((vec!(b'b', b'b'), true)));
+
+    let mut rng: StdRng = SeedableRng::from_seed(&[4u, 1, 1]);
+    for _ in range(0, 20u) {
+        let word = dfa.sample_word(5, &mut rng).unwrap();
+        assert_eq!(word.len(), 5);
+        assert_eq!(dfa.run(word.as_slice()).unwrap(), true);
+    }
+
+    match DFA::new(1, &alphabet, &vec!((0, b'a', 0), (0, b'c', 0)), 0, &vec!()) {
+        Err(e) => assert_eq!(e, UnknownSymbol((0, b'c', 0))),
+        Ok(_) => fail!("Symbols outside the alphabet should be rejected")
+    }
+
+    //Only chars are shown in quotes
+    match DFA::new(1, &alphabet, &vec!((0, b'a', 0), (0, b'a', 0)), 0, &vec!()) {
+        Err(e) => assert_eq!(format!("{}", e).as_slice(), "Duplicate transition: (0, 97) -> 0"),
+        Ok(_) => fail!("Duplicate transitions should be rejected")
+    }
+}
+
+#[test]
+fn range_dfa_identifiers() {
+    //Accepts identifiers over printable ASCII: a letter or '_', then letters, digits or '_'
+    let alphabet = vec!((' ', '~'));
+    let word = [('a', 'z'), ('A', 'Z'), ('_', '_')];
+    let other = [(' ', '/'), (':', '@'), ('[', '^'), ('`', '`'), ('{', '~')];
+    let mut transitions = vec!((0, ('0', '9'), 2), (1, ('0', '9'), 1), (2, (' ', '~'), 2));
+    for &r in word.iter() {
+        transitions.push((0, r, 1));
+        transitions.push((1, r, 1));
+    }
+
+    for &r in other.iter() {
+        transitions.push((0, r, 2));
+        transitions.push((1, r, 2));
+    }
+
+    let mut ids = RangeDFA::new(3, &alphabet, &transitions, 0, &vec!(1)).unwrap();
+    assert_eq!(ids.num_classes(), 9);
+
+    for s in ["foo", "_bar_1", "Baz9"].iter() {
+        assert_eq!(ids.run(*s).unwrap(), true);
+    }
+
+    for s in ["", "1abc", "foo bar", "a-b"].iter() {
+        assert_eq!(ids.run(*s).unwrap(), false);
+    }
+
+    assert!(ids.run("caf\u00e9").is_none());
+
+    //Letters and '_' behave the same, as do all the other symbols
+    let original = ids.clone();
+    ids.minimize();
+    assert_eq!(ids.num_states(), 3);
+    assert_eq!(ids.num_classes(), 3);
+    assert!(ids.equivalent(&original));
+    assert!(ids.to_dfa() == original.to_dfa());
+
+    //Strings without uppercase letters
+    let lower = RangeDFA::new_partial(1, &alphabet, &vec!((0, (' ', '@'), 0), (0, ('[', '~'), 0)),
+                                      0, &vec!(0)).unwrap();
+    let lower_ids = ids.intersect(&lower);
+    assert_eq!(lower_ids.run("foo_1").unwrap(), true);
+    assert_eq!(lower_ids.run("Foo").unwrap(), false);
+    assert_eq!(ids.difference(&lower).run("fOo").unwrap(), true);
+    assert_eq!(ids.union(&lower).run("1 + 1").unwrap(), true);
+    assert!(ids.union(&lower).complement().run("1 + A").unwrap());
+    assert!(lower_ids.difference(&ids).is_empty());
+}
+
+#[test]
+fn range_dfa_rejects_bad_ranges() {
+    let alphabet = vec!(('a', 'z'));
+    let cases = vec!(
+        (vec!((0, ('a', 'm'), 0)), MissingTransition(0, 'n')),
+        (vec!((0, ('a', 'm'), 0), (0, ('h', 'z'), 0)), DuplicateTransition((0, 'h', 0))),
+        (vec!((0, ('a', '~'), 0)), UnknownSymbol((0, '{', 0))),
+        (vec!((0, ('z', 'a'), 0)), InvalidRange('z', 'a'))
+    );
+
+    for &(ref transitions, ref error) in cases.iter() {
+        match RangeDFA::new(1, &alphabet, transitions, 0, &vec!(0)) {
+            Err(e) => assert_eq!(e, *error),
+            Ok(_) => fail!("Expected {}", error)
+        }
+    }
+}
+
+#[test]
+fn dfa_alphabet_order_does_not_matter() {
+    //Both accept strings with an odd number of 'a's, with the alphabet listed in different orders
+    let odd = DFA::new(2, &vec!('a', 'b'), &vec!((0, 'a', 1), (0, 'b', 0), (1, 'a', 0), (1, 'b', 1)),
+                       0, &vec!(1)).unwrap();
+    let reordered = DFA::new(2, &vec!('b', 'a'), &vec!((0, 'b', 0), (0, 'a', 1), (1, 'b', 1), (1, 'a', 0)),
+                             0, &vec!(1)).unwrap();
+
+    assert!(odd.equivalent_strict(&reordered));
+    let both = odd.intersect(&reordered).unwrap();
+    assert_eq!(both.run("abba"), Some(false));
+    assert_eq!(both.run("bab"), Some(true));
+    assert_eq!(both.run("abc"), None);
+}
+
+#[test]
+fn dfa_distinguishing_string() {
+    let a = vec!('a', 'b');
+    let t = vec!((0, 'a', 1), (0, 'b', 1),
+                 (1, 'a', 0), (1, 'b', 0));
+
+    let even = DFA::new(2, &a, &t, 0, &vec!(0)).unwrap();
+    let all = DFA::new(2, &a, &t, 0, &vec!(0, 1)).unwrap();
+
+    assert_eq!(even.distinguishing_string(&all), Some(("a".to_string(), false)));
+    assert_eq!(all.distinguishing_string(&even), Some(("a".to_string(), true)));
+    assert_eq!(even.distinguishing_string(&even.clone()), None);
+
+    //Strings of only a's, over different alphabets
+    let only_a = DFA::new(1, &vec!('a'), &vec!((0, 'a', 0)), 0, &vec!(0)).unwrap();
+    let also_only_a = DFA::new(2, &a, &vec!((0, 'a', 0), (0, 'b', 1),
+                                            (1, 'a', 1), (1, 'b', 1)), 0, &vec!(0)).unwrap();
+
+    assert_eq!(only_a.distinguishing_string(&also_only_a), None);
+    assert_eq!(only_a.distinguishing_string(&even), Some(("a".to_string(), true)));
+}
+
+#[test]
+fn dfa_subset() {
+    let a = vec!('a', 'b');
+    let t = vec!((0, 'a', 1), (0, 'b', 1),
+                 (1, 'a', 0), (1, 'b', 0));
+
+    let even = DFA::new(2, &a, &t, 0, &vec!(0)).unwrap();
+    let all = DFA::new(2, &a, &t, 0, &vec!(0, 1)).unwrap();
+
+    assert_eq!(even.is_subset_of(&all), Ok(()));
+    assert_eq!(even.is_subset_of(&even), Ok(()));
+    assert_eq!(all.is_subset_of(&even), Err("a".to_string()));
+}
+
 ///////////////////////////
 ////  NFA Unit Tests  /////
 ///////////////////////////
@@ -257,8 +932,191 @@ fn nfa_validates_transitions() {
 
     match DFA::new(states, &alphabet, &t, start, &accept) {
         Ok(_) => fail!(),
-        Err(e) => assert_eq!(e, format!("In transition: (0, '1') -> 5: State `5` does not exist"))
+        Err(e) => assert_eq!(e, StateOutOfRange((0, '1', 5), 5))
     }
+
+    match NFA::new(states, &alphabet, &t, start, &accept) {
+        Ok(_) => fail!(),
+        Err(e) => assert_eq!(e, StateOutOfRange((0, '1', 5), 5))
+    }
+
+    //Transitions on '_' are reported as written, in their given order
+    let t4 = vec!((0, '_', 5), (0, '2', 0));
+    match NFA::new(states, &alphabet, &t4, start, &accept) {
+        Ok(_) => fail!(),
+        Err(e) => assert_eq!(e, StateOutOfRange((0, '_', 5), 5))
+    }
+
+    let report = NFA::validate(states, &alphabet, &t4, start, &accept);
+    assert_eq!(report.errors, vec!(StateOutOfRange((0, '_', 5), 5), UnknownSymbol((0, '2', 0))));
+
+    match NFA::new(states, &vec!('0', '_'), &vec!(), start, &accept) {
+        Ok(_) => fail!(),
+        Err(e) => {
+            assert_eq!(e, ReservedSymbol('_'));
+            assert_eq!(format!("{}", e).as_slice(), "Alphabets cannot contain '_'");
+        }
+    }
+}
+
+#[test]
+fn nfa_validation_reports_every_problem() {
+    let alphabet = vec!('a', '_');
+    let t = vec!((0, '_', 1), (0, 'b', 1), (1, 'a', 4));
+    let report = NFA::validate(3, &alphabet, &t, 0, &vec!(1));
+
+    assert_eq!(report.errors, vec!(ReservedSymbol('_'),
+                                   UnknownSymbol((0, 'b', 1)),
+                                   StateOutOfRange((1, 'a', 4), 4)));
+    assert_eq!(report.warnings, vec!(UnreachableState(2)));
+
+    match NFA::new(1, &vec!('a'), &vec!(), 3, &vec!(0)) {
+        Ok(_) => fail!(),
+        Err(e) => assert_eq!(e, InvalidStart(3))
+    }
+
+    assert!(NFA::validate(1, &vec!('a'), &vec!((0, 'a', 0)), 0, &vec!(0)).is_valid());
+
+    //With epsilons given apart, '_' is an ordinary symbol
+    let report = NFA::validate_with_epsilons(3, &alphabet, &t, &vec!((1, 2), (2, 7)), 0, &vec!(1));
+    assert_eq!(report.errors, vec!(UnknownSymbol((0, 'b', 1)),
+                                   StateOutOfRange((1, 'a', 4), 4),
+                                   EpsilonOutOfRange((2, 7), 7)));
+    assert_eq!(report.warnings, vec!(DeadState(2)));
+
+    let report = NFA::validate(3, &vec!('a'), &vec!((0, '_', 3)), 0, &vec!(0));
+    assert_eq!(report.errors, vec!(StateOutOfRange((0, '_', 3), 3)));
+}
+
+#[test]
+fn nfa_builder_names_states() {
+    //Strings ending in ab
+    let mut builder = NfaBuilder::new(&vec!('a', 'b'));
+    builder.start_state("start").state("saw a").accept_state("done")
+           .transition("start", 'a', "start").transition("start", 'b', "start")
+           .transition("start", 'a', "saw a").transition("saw a", 'b', "done");
+
+    let nfa = builder.build().unwrap();
+    assert_eq!(nfa.run("bab"), Some(true));
+    assert_eq!(nfa.run("ba"), Some(false));
+    assert_eq!(nfa.trace("ab"), Some(vec!(vec!("start".to_string()),
+                                          vec!("start".to_string(), "saw a".to_string()),
+                                          vec!("start".to_string(), "done".to_string()))));
+
+    builder.epsilon("done", "start");
+    let looped = builder.build().unwrap();
+    assert_eq!(looped.trace("ab").unwrap().get(2).len(), 2);
+    assert_eq!(looped.find_state("done"), Some(2));
+}
+
+#[test]
+fn nfa_macro_builds_named_nfa() {
+    //a*b*, with an epsilon transition between the loops
+    let nfa = nfa!{
+        start p;
+        accept q;
+        p -a-> p;
+        p --> q;
+        q -b-> q;
+    }.unwrap();
+
+    assert_eq!(nfa.run("aabb"), Some(true));
+    assert_eq!(nfa.run(""), Some(true));
+    assert_eq!(nfa.run("ba"), Some(false));
+    assert_eq!(nfa.trace("a"), Some(vec!(vec!("p".to_string(), "q".to_string()),
+                                         vec!("p".to_string(), "q".to_string()))));
+}
+
+#[test]
+fn nfa_epsilons_are_separate_from_symbols() {
+    //Identifiers of letters and underscores that start with a letter
+    let alphabet = vec!('a', '_');
+    let t = vec!((0, 'a', 1), (1, 'a', 2), (1, '_', 2));
+    let nfa = NFA::with_epsilons(3, &alphabet, &t, &vec!((2, 1)), 0, &vec!(1)).unwrap();
+
+    assert_eq!(nfa.run("a_a"), Some(true));
+    assert_eq!(nfa.run("_a"), Some(false));
+    assert!(nfa.to_dfa() == regex::compile("a[a_]*", &alphabet).unwrap().to_dfa());
+
+    match NFA::with_epsilons(3, &alphabet, &t, &vec!((2, 3)), 0, &vec!(1)) {
+        Ok(_) => fail!(),
+        Err(e) => {
+            assert_eq!(e, EpsilonOutOfRange((2, 3), 3));
+            assert_eq!(format!("{}", e).as_slice(), "In epsilon transition: 2 -> 3: State `3` does not exist");
+        }
+    }
+
+    //The builder keeps epsilon transitions apart from transitions on '_'
+    let mut builder = NfaBuilder::new(&alphabet);
+    builder.start_state("p").accept_state("q").transition("p", '_', "q").epsilon("q", "p");
+    let underscores = builder.build().unwrap();
+    assert_eq!(underscores.run("__"), Some(true));
+    assert_eq!(underscores.run(""), Some(false));
+}
+
+#[deriving(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Show)]
+enum Event {
+    Open,
+    Data,
+    Close
+}
+
+#[test]
+fn nfa_over_enum_symbols() {
+    //Accepts sessions that open, send any amount of data, and close
+    let alphabet = vec!(Open, Data, Close);
+    let transitions = vec!((0, Open, 1), (1, Data, 1), (1, Close, 2));
+    let nfa = NFA::with_epsilons(3, &alphabet, &transitions, &vec!((2, 0)), 0, &vec!(2)).unwrap();
+
+    assert_eq!(nfa.run([Open, Close].as_slice()).unwrap(), true);
+    assert_eq!(nfa.run([Open, Data, Data, Close, Open, Close].as_slice()).unwrap(), true);
+    assert_eq!(nfa.run([Open, Data].as_slice()).unwrap(), false);
+    assert_eq!(nfa.run([Data].as_slice()).unwrap(), false);
+    assert!(!nfa.is_finite());
+
+    let dfa = nfa.to_dfa();
+    assert_eq!(dfa.run([Open, Data, Close].as_slice()).unwrap(), true);
+    assert_eq!(dfa.run([Close].as_slice()).unwrap(), false);
+    let one: BigUint = FromPrimitive::from_uint(1).unwrap();
+    assert_eq!(dfa.count_accepted(3), one);
+
+    let words: Vec<Vec<Event>> = nfa.accepted_words_up_to(4).collect();
+    assert_eq!(words, vec!(vec!(Open, Close), vec!(Open, Data, Close),
+                           vec!(Open, Data, Data, Close), vec!(Open, Close, Open, Close)));
+
+    //A single session is a subset, but not the other way around
+    let single = NFA::with_epsilons(3, &alphabet, &transitions, &vec!(), 0, &vec!(2)).unwrap();
+    assert_eq!(single.subset_witness(&nfa), None);
+    let witness = nfa.subset_witness(&single).unwrap();
+    assert_eq!(nfa.run(witness.as_slice()).unwrap(), true);
+    assert_eq!(single.run(witness.as_slice()).unwrap(), false);
+}
+
+#[test]
+fn nfa_reverse() {
+    let alphabet = vec!('a', 'b', 'c');
+    let nfa = regex::compile("ab*c|c", &alphabet).unwrap();
+    let reversed = nfa.reverse();
+
+    for s in ["cba", "cbbba", "ca", "c"].iter() {
+        assert_eq!(reversed.run(*s).unwrap(), true);
+    }
+
+    for s in ["abc", "ac", "", "cc"].iter() {
+        assert_eq!(reversed.run(*s).unwrap(), false);
+    }
+
+    assert!(reversed.reverse().to_dfa() == nfa.to_dfa());
+
+    //Starting from both ends of "ab*c" accepts its suffixes
+    let dfa = DFA::new(3, &alphabet, &vec!((0, 'a', 1), (0, 'b', 2), (0, 'c', 2),
+                                           (1, 'a', 2), (1, 'b', 1), (1, 'c', 0),
+                                           (2, 'a', 2), (2, 'b', 2), (2, 'c', 2)),
+                       0, &vec!(0)).unwrap();
+    let suffixes = dfa.to_nfa().to_dfa_from(&vec!(0, 1));
+    assert_eq!(suffixes.run("bbc").unwrap(), true);
+    assert_eq!(suffixes.run("abcabc").unwrap(), true);
+    assert_eq!(suffixes.run("ba").unwrap(), false);
 }
 
 #[test]
@@ -321,4 +1179,391 @@ fn nfa_epsilon_transitions() {
     	Some(b) => assert_eq!(b, false),
     	None => fail!()
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn nfa_to_dfa_accepts_same_language() {
+    let alphabet = vec!('a', 'b');
+    let states = 4;
+    let start = 0;
+    let accept = vec!(3);
+    //Accepts strings of the form a(b*)a(b*)a
+    let t = vec!((0, 'a', 1),
+                 (1, 'a', 2), (1, 'b', 1),
+                 (2, 'a', 3), (2, 'b', 2));
+
+    let nfa = NFA::new(states, &alphabet, &t, start, &accept).unwrap();
+    let dfa = nfa.to_dfa();
+
+    let strings = vec!("", "a", "aa", "aaa", "abbbaba", "ababa", "abbbabab", "baba", "aaaa");
+    for s in strings.iter() {
+        assert_eq!(nfa.run(*s), dfa.run(*s));
+    }
+}
+
+#[test]
+fn nfa_to_dfa_follows_epsilons() {
+    let alphabet = vec!('0', '1');
+    let states = 3;
+    let start = 0;
+    let accept = vec!(2);
+    //Accepts any string ending in 1
+    let t = vec!((0, '0', 0), (0, '1', 0), (0, '1', 1), (1, '_', 2));
+
+    let nfa = NFA::new(states, &alphabet, &t, start, &accept).unwrap();
+
+    let expected = DFA::new(2, &alphabet, &vec!((0, '0', 0), (0, '1', 1),
+                                                 (1, '0', 0), (1, '1', 1)), 0, &vec!(1)).unwrap();
+
+    assert_eq!(nfa.to_dfa(), expected);
+}
+
+#[test]
+fn nfa_concat_and_union() {
+    let alphabet = vec!('a', 'b');
+    let a = NFA::new(2, &alphabet, &vec!((0, 'a', 1)), 0, &vec!(1)).unwrap();
+    let b = NFA::new(2, &alphabet, &vec!((0, 'b', 1)), 0, &vec!(1)).unwrap();
+
+    let ab = a.concat(&b);
+    assert_eq!(ab.run("ab").unwrap(), true);
+    for s in vec!("", "a", "b", "ba", "abb").iter() {
+        assert_eq!(ab.run(*s).unwrap(), false);
+    }
+
+    let a_or_b = a.union(&b);
+    assert_eq!(a_or_b.run("a").unwrap(), true);
+    assert_eq!(a_or_b.run("b").unwrap(), true);
+    for s in vec!("", "ab", "ba", "aa").iter() {
+        assert_eq!(a_or_b.run(*s).unwrap(), false);
+    }
+}
+
+#[test]
+fn nfa_repetition() {
+    let alphabet = vec!('a', 'b');
+    //Accepts only "ab"
+    let ab = NFA::new(3, &alphabet, &vec!((0, 'a', 1), (1, 'b', 2)), 0, &vec!(2)).unwrap();
+
+    let star = ab.star();
+    let plus = ab.plus();
+    let opt = ab.optional();
+
+    for s in vec!("ab", "abab", "ababab").iter() {
+        assert_eq!(star.run(*s).unwrap(), true);
+        assert_eq!(plus.run(*s).unwrap(), true);
+    }
+
+    for s in vec!("a", "aba", "abb", "ba").iter() {
+        assert_eq!(star.run(*s).unwrap(), false);
+        assert_eq!(plus.run(*s).unwrap(), false);
+        assert_eq!(opt.run(*s).unwrap(), false);
+    }
+
+    assert_eq!(star.run("").unwrap(), true);
+    assert_eq!(plus.run("").unwrap(), false);
+    assert_eq!(opt.run("").unwrap(), true);
+    assert_eq!(opt.run("ab").unwrap(), true);
+    assert_eq!(opt.run("abab").unwrap(), false);
+}
+
+
+#[test]
+fn nfa_subset() {
+    let alphabet = vec!('a', 'b');
+    let ab = regex::compile("(ab)*", &alphabet).unwrap();
+    let a_or_b = regex::compile("(a|b)*", &alphabet).unwrap();
+    let even = regex::compile("((a|b)(a|b))*", &alphabet).unwrap();
+
+    assert_eq!(ab.is_subset_of(&a_or_b), Ok(()));
+    assert_eq!(ab.is_subset_of(&even), Ok(()));
+
+    match even.is_subset_of(&ab) {
+        Ok(_) => fail!(),
+        Err(w) => {
+            assert_eq!(even.run(w.as_slice()).unwrap(), true);
+            assert_eq!(ab.run(w.as_slice()).unwrap(), false);
+        }
+    }
+
+    //Strings using symbols other lacks are never accepted by other
+    let only_a = regex::compile("a*", &vec!('a')).unwrap();
+    assert_eq!(only_a.is_subset_of(&a_or_b), Ok(()));
+    assert_eq!(a_or_b.is_subset_of(&only_a), Err("b".to_string()));
+}
+
+#[test]
+fn nfa_enumerates_without_duplicates() {
+    let alphabet = vec!('a', 'b');
+    //Ambiguous: "a" and "aa" match both alternatives
+    let nfa = regex::compile("a*|aa?|b", &alphabet).unwrap();
+
+    let all: Vec<String> = nfa.accepted_strings_up_to(3).collect();
+    let expected: Vec<String> = vec!("", "a", "b", "aa", "aaa").iter().map(|s| s.to_string()).collect();
+    assert_eq!(all, expected);
+
+    let first: Vec<String> = nfa.accepted_strings().take(6).collect();
+    assert_eq!(first.last(), Some(&"aaaa".to_string()));
+}
+
+#[test]
+fn nfa_language_queries() {
+    let alphabet = vec!('a', 'b');
+    let finite = regex::compile("(ab?|ε)(b|ε)", &alphabet).unwrap();
+    let infinite = regex::compile("a(ba)*", &alphabet).unwrap();
+    let all = regex::compile("(a*b*)*", &alphabet).unwrap();
+    let none = regex::compile("a∅", &alphabet).unwrap();
+
+    assert!(finite.is_finite() && !infinite.is_finite() && !all.is_finite() && none.is_finite());
+    assert!(none.is_empty() && !finite.is_empty() && !all.is_empty());
+    assert!(all.is_universal() && !infinite.is_universal() && !none.is_universal());
+
+    let eps_loop = NFA::new(2, &alphabet, &vec!((0, '_', 1), (1, '_', 0), (1, 'a', 1)), 0, &vec!(0)).unwrap();
+    assert!(!eps_loop.is_finite());
+    let eps_only = NFA::new(2, &alphabet, &vec!((0, '_', 1), (1, '_', 0), (1, 'a', 0)), 1, &vec!(0)).unwrap();
+    assert!(!eps_only.is_finite());
+    //Epsilon cycles do not make a language infinite
+    let eps_finite = NFA::new(3, &alphabet, &vec!((0, '_', 1), (1, '_', 0), (1, 'b', 2)), 0, &vec!(2)).unwrap();
+    assert!(eps_finite.is_finite());
+
+    assert_eq!(finite.longest_accepted_length(), Some(3));
+    assert_eq!(eps_finite.longest_accepted_length(), Some(1));
+    assert_eq!(infinite.longest_accepted_length(), None);
+}
+
+///////////////////////////
+//// Regex Unit Tests /////
+///////////////////////////
+
+#[test]
+fn regex_compiles_to_nfa() {
+    let alphabet = vec!('a', 'b', 'c');
+    let nfa = regex::compile("a(b|c)*a?", &alphabet).unwrap();
+
+    for s in vec!("a", "aa", "ab", "abcbc", "acca").iter() {
+        assert_eq!(nfa.run(*s).unwrap(), true);
+    }
+
+    for s in vec!("", "b", "aab", "abaa", "ca").iter() {
+        assert_eq!(nfa.run(*s).unwrap(), false);
+    }
+}
+
+#[test]
+fn regex_classes_and_literals() {
+    let alphabet = vec!('a', 'b', 'c', '*');
+
+    let range = regex::compile("[a-b]+", &alphabet).unwrap();
+    assert_eq!(range.run("abba").unwrap(), true);
+    assert_eq!(range.run("abc").unwrap(), false);
+
+    let negated = regex::compile("[^a*]", &alphabet).unwrap();
+    assert_eq!(negated.run("b").unwrap(), true);
+    assert_eq!(negated.run("c").unwrap(), true);
+    assert_eq!(negated.run("a").unwrap(), false);
+
+    let escaped = regex::compile("a\\*", &alphabet).unwrap();
+    assert_eq!(escaped.run("a*").unwrap(), true);
+    assert_eq!(escaped.run("a").unwrap(), false);
+
+    let any = regex::compile(".ε.", &alphabet).unwrap();
+    assert_eq!(any.run("c*").unwrap(), true);
+    assert_eq!(any.run("c").unwrap(), false);
+
+    let none = regex::compile("a∅|b", &alphabet).unwrap();
+    assert_eq!(none.run("a").unwrap(), false);
+    assert_eq!(none.run("b").unwrap(), true);
+}
+
+#[test]
+fn regex_reports_error_positions() {
+    let alphabet = vec!('a', 'b');
+    let errors = vec!(("ab)", 2u, "Unmatched `)`"),
+                      ("a(b", 1, "Unclosed `(`"),
+                      ("ab*c", 3, "Symbol `c` is not in the alphabet"),
+                      ("a|*", 2, "`*` does not follow an expression"),
+                      ("[ba", 0, "Unclosed `[`"),
+                      ("a[b-a]", 2, "Invalid range `b-a`"),
+                      ("[]", 0, "Empty character class"));
+
+    for &(pattern, position, message) in errors.iter() {
+        match regex::compile(pattern, &alphabet) {
+            Ok(_) => fail!(),
+            Err(e) => {
+                assert_eq!(e.position, position);
+                assert_eq!(e.message.as_slice(), message);
+            }
+        }
+    }
+}
+
+#[test]
+fn dfa_to_regex_prints_simplified_expression() {
+    let alphabet = vec!('a', 'b');
+    //Accepts strings of only a's
+    let only_a = DFA::new(2, &alphabet, &vec!((0, 'a', 0), (0, 'b', 1),
+                                               (1, 'a', 1), (1, 'b', 1)), 0, &vec!(0)).unwrap();
+    assert_eq!(format!("{}", only_a.to_regex()).as_slice(), "a*");
+
+    //Accepts all even length strings
+    let even = DFA::new(2, &alphabet, &vec!((0, 'a', 1), (0, 'b', 1),
+                                             (1, 'a', 0), (1, 'b', 0)), 0, &vec!(0)).unwrap();
+    assert_eq!(format!("{}", even.to_regex()).as_slice(), "([ab][ab])*");
+
+    let none = even.intersect(&even.complement()).unwrap();
+    assert_eq!(format!("{}", none.to_regex()).as_slice(), "∅");
+}
+
+#[test]
+fn dfa_to_regex_round_trips() {
+    let alphabet = vec!('a', 'b', 'c');
+    let patterns = vec!("a(b|c)*a?", "(ab|ba)*c+", "[^a]?a*|cc", "ε", "(a|b)*abb");
+    let orders = vec!(regex::InOrder, regex::FewestPaths, regex::LeastWeight);
+
+    for p in patterns.iter() {
+        let dfa = regex::compile(*p, &alphabet).unwrap().to_dfa();
+        for order in orders.iter() {
+            let printed = format!("{}", dfa.to_regex_with(order.clone()));
+            let reparsed = regex::compile(printed.as_slice(), &alphabet).unwrap().to_dfa();
+            assert_eq!(reparsed, dfa);
+        }
+    }
+}
+
+///////////////////////////
+////  SFA Unit Tests  /////
+///////////////////////////
+
+#[test]
+fn sfa_identifiers() {
+    let letter = CharSet::alphabetic();
+    let letter_or_digit = letter.or(&CharSet::digit());
+    let ident = SFA::new(2, &vec!((0, letter, 1), (1, letter_or_digit, 1)), 0, &vec!(1)).unwrap();
+
+    assert!(ident.is_deterministic());
+    assert_eq!(ident.run("abc1"), Some(true));
+    assert_eq!(ident.run("\u00e9t\u00e9"), Some(true));
+    assert_eq!(ident.run("1abc"), Some(false));
+    assert_eq!(ident.run(""), Some(false));
+}
+
+#[test]
+fn sfa_operations() {
+    let any: CharSet = Predicate::top();
+    //Accepts strings containing "ab"
+    let mut has_ab = SFA::new(3, &vec!((0, any.clone(), 0), (0, CharSet::range('a', 'a'), 1),
+                                       (1, CharSet::range('b', 'b'), 2), (2, any.clone(), 2)),
+                              0, &vec!(2)).unwrap();
+    assert!(!has_ab.is_deterministic());
+
+    let det = has_ab.determinize();
+    assert!(det.is_deterministic());
+    for s in vec!("ab", "xxaby", "aab", "ba", "a", "\u00e9ab").iter() {
+        assert_eq!(det.run(*s), has_ab.run(*s));
+    }
+
+    let ident = SFA::new(2, &vec!((0, CharSet::alphabetic(), 1), (1, CharSet::alphabetic(), 1)),
+                         0, &vec!(1)).unwrap();
+    let both = has_ab.intersect(&ident);
+    assert_eq!(both.run("cabbage"), Some(true));
+    assert_eq!(both.run("c4bbage"), Some(false));
+    assert_eq!(both.run("ab1"), Some(false));
+    assert_eq!(has_ab.union(&ident).run("1ab"), Some(true));
+
+    assert!(has_ab.difference(&has_ab).is_empty());
+    assert!(has_ab.complement().intersect(&has_ab).is_empty());
+    assert!(!has_ab.complement().is_empty());
+
+    let copy = has_ab.clone();
+    has_ab.minimize();
+    assert_eq!(has_ab.num_states(), 3);
+    assert!(has_ab.equivalent(&copy));
+    assert!(!has_ab.equivalent(&ident));
+}
+
+#[test]
+fn charset_algebra() {
+    let any: CharSet = Predicate::top();
+    assert!(!any.not().is_satisfiable());
+
+    let merged = CharSet::new(&vec!(('a', 'f'), ('d', 'k'), ('z', 'a')));
+    assert_eq!(merged, CharSet::range('a', 'k'));
+    assert_eq!(format!("{}", merged).as_slice(), "['a'-'k']");
+
+    let vowels = CharSet::new(&vec!(('a', 'a'), ('e', 'e'), ('i', 'i'), ('o', 'o'), ('u', 'u')));
+    let consonants = CharSet::range('a', 'z').and(&vowels.not());
+    assert!(consonants.contains('b'));
+    assert!(!consonants.contains('e'));
+    assert!(!consonants.and(&vowels).is_satisfiable());
+    assert_eq!(consonants.or(&vowels), CharSet::range('a', 'z'));
+}
+
+///////////////////////////
+////    Benchmarks    /////
+///////////////////////////
+
+//Accepts log lines mentioning an error
+fn error_filter() -> (DFA, Vec<char>, String) {
+    let mut alphabet: Vec<char> = range('a' as u8, 'z' as u8 + 1).map(|b| b as char).collect();
+    alphabet.push_all(['0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ' ', ':']);
+    let mut dfa = regex::compile(".*error.*", &alphabet).unwrap().to_dfa();
+    dfa.minimize();
+
+    let mut line = String::new();
+    for _ in range(0, 100u) {
+        line.push_str("status 200 served in 12ms from cache ");
+    }
+
+    line.push_str("error: timeout");
+    (dfa, alphabet, line)
+}
+
+//Running the filter on the dense transition table, driven straight from the input's chars
+#[bench]
+fn dfa_run_after_dense_table(b: &mut Bencher) {
+    let (dfa, _, line) = error_filter();
+    b.bytes = line.len() as u64;
+    b.iter(|| dfa.run(line.as_slice()));
+}
+
+//The run used before the dense transition table, kept verbatim for comparison:
+//the input is collected into a Vec, each step is a lookup in a HashMap keyed by
+//(state, symbol), and a missing transition checks the alphabet for unknown symbols
+fn run_before_hashmap<S: Symbol, I: Word<S>>(
+    delta: &HashMap<(uint, S), uint>,
+    alphabet: &Vec<S>,
+    dfa: &DFA<S>,
+    input: I
+) -> Option<bool> {
+    let mut curr_state = Some(dfa.start_state());
+
+    for sym in input.symbols().move_iter() {
+         curr_state = match curr_state.and_then(|s| delta.find_copy(&(s, sym.clone()))) {
+            Some(v) => Some(v),
+            None if alphabet.contains(&sym) => None,
+            None => return None
+         };
+    }
+
+    Some(match curr_state {
+        Some(s) => dfa.is_accept_state(s),
+        None => false
+    })
+}
+
+#[bench]
+fn dfa_run_before_hashmap(b: &mut Bencher) {
+    let (dfa, alphabet, line) = error_filter();
+
+    let mut delta = HashMap::new();
+    for s in range(0, dfa.num_states()) {
+        for &c in alphabet.iter() {
+            match dfa.next_state(s, &c) {
+                Some(next) => { delta.insert((s, c), next); },
+                None => {}
+            }
+        }
+    }
+
+    b.bytes = line.len() as u64;
+    b.iter(|| run_before_hashmap(&delta, &alphabet, &dfa, line.as_slice()));
+}
@@ -0,0 +1,100 @@
+use std::collections::bitv::BitvSet;
+use super::Symbol;
+use super::graph;
+use super::error::{AutomatonError, ValidationReport, UnknownSymbol, StateOutOfRange, EpsilonOutOfRange};
+use super::error::{InvalidStart, InvalidAccept};
+use super::error::{UnreachableState, DeadState};
+
+//Checks shared by DFA and NFA validation
+
+//A transition as the caller wrote it, with no symbol for an epsilon transition
+pub type Written<S> = (uint, Option<S>, uint);
+
+//Reports transitions on unknown symbols or states in the given order, and returns the transitions
+//that passed. out_of_range gives the error for a transition on the given state, which does not
+//exist, so each automaton can report it the way it was written.
+pub fn check_transitions<S: Symbol, I: Iterator<Written<S>>>(
+    report: &mut ValidationReport<S>,
+    num_states: uint,
+    alphabet: &Vec<S>,
+    mut transitions: I,
+    out_of_range: |&Written<S>, uint| -> AutomatonError<S>
+) -> Vec<Written<S>> {
+
+    let mut valid = vec!();
+    for t in transitions {
+        let mut ok = true;
+        match t {
+            (curr, Some(ref sym), next) if !alphabet.contains(sym) => {
+                report.errors.push(UnknownSymbol((curr, sym.clone(), next)));
+                ok = false;
+            },
+            _ => {}
+        }
+
+        let (curr, next) = match t { (curr, _, next) => (curr, next) };
+        for &state in [curr, next].iter() {
+            if state >= num_states {
+                report.errors.push(out_of_range(&t, state));
+                ok = false;
+            }
+        }
+
+        if ok {
+            valid.push(t);
+        }
+    }
+
+    valid
+}
+
+//The error for a transition on the given state, which does not exist
+pub fn out_of_range<S: Clone>(t: &Written<S>, state: uint) -> AutomatonError<S> {
+    match *t {
+        (curr, Some(ref sym), next) => StateOutOfRange((curr, sym.clone(), next), state),
+        (curr, None, next) => EpsilonOutOfRange((curr, next), state)
+    }
+}
+
+//Reports invalid start and accept states, then warns about unreachable and dead states
+//using the valid transitions, given as (current state, next state) pairs.
+pub fn check_states<S>(report: &mut ValidationReport<S>, num_states: uint, start: uint,
+                       accept: &Vec<uint>, valid: &Vec<(uint, uint)>) {
+    let mut accept_states = BitvSet::new();
+    for &s in accept.iter() {
+        if s >= num_states {
+            report.errors.push(InvalidAccept(s));
+        }
+
+        else {
+            accept_states.insert(s);
+        }
+    }
+
+    if start >= num_states {
+        report.errors.push(InvalidStart(start));
+        return;
+    }
+
+    let mut forward = Vec::from_elem(num_states, vec!());
+    let mut backward = Vec::from_elem(num_states, vec!());
+    for &(curr, next) in valid.iter() {
+        forward.get_mut(curr).push(next);
+        backward.get_mut(next).push(curr);
+    }
+
+    let mut start_set = BitvSet::new();
+    start_set.insert(start);
+    let reachable = graph::reachable(&forward, &start_set);
+    let coreachable = graph::reachable(&backward, &accept_states);
+
+    for s in range(0, num_states) {
+        if !reachable.contains(&s) {
+            report.warnings.push(UnreachableState(s));
+        }
+
+        else if !coreachable.contains(&s) {
+            report.warnings.push(DeadState(s));
+        }
+    }
+}